@@ -0,0 +1,66 @@
+/*
+ * The bitboard against the Sudoku based solvers, each running single position and
+ * naked sets over every sudoku in top95 until they stop getting anywhere. Run with
+ *
+ *     cargo bench
+ *
+ * and criterion keeps the last run in target/criterion to compare the next against.
+ */
+use criterion::{criterion_group, criterion_main, Criterion};
+use rust_sudoku_solver::bitboard::BitBoard;
+use rust_sudoku_solver::reader::SudokuReader;
+use rust_sudoku_solver::solvers;
+use rust_sudoku_solver::sudoku::Sudoku;
+use std::fs::File;
+use std::hint::black_box;
+use std::io::BufReader;
+
+fn top95() -> Vec<Sudoku> {
+    let file = File::open("test/top95.txt").expect("Run from the top of the repo");
+    SudokuReader::new(BufReader::new(file))
+        .map(|sudoku| sudoku.unwrap())
+        .collect()
+}
+
+// The old way, the same as the bitboard's propagate but on the Sudoku itself.
+fn propagate_sudoku(sudoku: &mut Sudoku) {
+    loop {
+        let before = *sudoku;
+        solvers::single_position(sudoku);
+        solvers::naked_set(sudoku);
+        if before == *sudoku {
+            return;
+        }
+    }
+}
+
+fn propagate(c: &mut Criterion) {
+    let sudokus = top95();
+    let mut group = c.benchmark_group("propagate top95");
+
+    group.bench_function("Sudoku", |b| {
+        b.iter(|| {
+            for sudoku in sudokus.iter() {
+                let mut copy = *sudoku;
+                propagate_sudoku(&mut copy);
+                black_box(copy);
+            }
+        })
+    });
+
+    // Including the trip there and back, as that's what anything using it pays.
+    group.bench_function("BitBoard", |b| {
+        b.iter(|| {
+            for sudoku in sudokus.iter() {
+                let mut board = BitBoard::from_sudoku(sudoku);
+                board.propagate().unwrap();
+                black_box(board.to_sudoku());
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, propagate);
+criterion_main!(benches);
@@ -0,0 +1,279 @@
+/*
+ * Solving a whole file of sudokus at once.
+ *
+ * Every sudoku is independent of the others and Copy, so we can just hand them out
+ * to a thread per core. Each thread grabs the next unsolved index off a shared
+ * counter rather than taking a fixed chunk, as a few hard puzzles bunched together
+ * would otherwise leave one thread doing all the work.
+ */
+use crate::bitboard::BitBoard;
+use crate::reader::{self, ParseError, Record};
+use crate::sudoku::Sudoku;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/**
+ * Unsolved
+ *
+ * Why a sudoku didn't get solved. Only a sudoku with exactly one solution counts as
+ * solved, as any of several would be a guess.
+ */
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Unsolved {
+    NoSolution,
+    ManySolutions,
+}
+
+// The solution to a sudoku, or why there isn't one.
+pub type Answer = Result<Sudoku, Unsolved>;
+
+/**
+ * solve_batch
+ *
+ * Solve every sudoku over the given number of threads, with the answers coming back
+ * in the same order as the input. The bitboard search is what finds the answer, as
+ * it has to run anyway to check there's only one.
+ */
+pub fn solve_batch(sudokus: &[Sudoku], threads: usize) -> Vec<Answer> {
+    assert!(threads > 0);
+    let next = AtomicUsize::new(0);
+    let mut results = vec![Err(Unsolved::NoSolution); sudokus.len()];
+
+    let solved: Vec<Vec<(usize, Answer)>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        if idx >= sudokus.len() {
+                            return done;
+                        }
+                        let solutions = BitBoard::from_sudoku(&sudokus[idx]).solutions(2);
+                        let answer = match solutions[..] {
+                            [] => Err(Unsolved::NoSolution),
+                            [solution] => Ok(solution.to_sudoku()),
+                            _ => Err(Unsolved::ManySolutions),
+                        };
+                        done.push((idx, answer));
+                    }
+                })
+            })
+            .collect();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    // Put everything back where it came from.
+    for (idx, answer) in solved.into_iter().flatten() {
+        results[idx] = answer;
+    }
+    results
+}
+
+/**
+ * solve_all
+ *
+ * solve_batch using every core the machine has.
+ */
+pub fn solve_all(sudokus: &[Sudoku]) -> Vec<Answer> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    solve_batch(sudokus, threads)
+}
+
+// How many lines to read in before solving them all at once.
+const CHUNK_SIZE: usize = 10000;
+
+// What gets written in place of a line that couldn't be read or solved, so every
+// line out still matches the line in.
+const FAILED_BOX: char = 'x';
+
+/**
+ * Problems
+ *
+ * The lines of a batch that couldn't be read, and the line numbers (from 1) of the
+ * sudokus with no solution or more than one.
+ */
+#[derive(PartialEq, Debug, Default)]
+pub struct Problems {
+    pub unreadable: Vec<ParseError>,
+    pub unsolvable: Vec<usize>,
+    pub many_solutions: Vec<usize>,
+}
+
+// A line of the input: a sudoku or why it couldn't be read, or None for a blank or
+// comment line to pass straight through along with its text.
+type Line = (Option<Result<Record, ParseError>>, String);
+
+/**
+ * solve_stream
+ *
+ * Read sudokus a line at a time, solve them a chunk at a time over every core and
+ * write out one line for each line in, in the same order. Blank and comment lines
+ * are written out as they were, and lines that can't be read or solved as 81 'x's,
+ * with what went wrong in the Problems.
+ */
+pub fn solve_stream<R: BufRead, W: Write>(
+    mut input: R,
+    output: &mut W,
+) -> std::io::Result<Problems> {
+    let mut problems = Problems::default();
+    let failed: String = std::iter::repeat_n(FAILED_BOX, 81).collect();
+    let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+    let mut bytes = Vec::new();
+    let mut line_number = 0;
+    let mut finished = false;
+
+    while !finished {
+        while chunk.len() < CHUNK_SIZE {
+            bytes.clear();
+            if input.read_until(b'\n', &mut bytes)? == 0 {
+                finished = true;
+                break;
+            }
+            line_number += 1;
+            let line: Line =
+                match reader::parse_record(line_number, &bytes, Sudoku::parse_line_metadata) {
+                    Some(record) => (Some(record), String::new()),
+                    None => (
+                        None,
+                        String::from_utf8_lossy(&bytes)
+                            .trim_end_matches(['\n', '\r'])
+                            .to_string(),
+                    ),
+                };
+            chunk.push(line);
+        }
+
+        let sudokus: Vec<Sudoku> = chunk
+            .iter()
+            .filter_map(|(record, _)| match record {
+                Some(Ok(record)) => Some(record.sudoku),
+                _ => None,
+            })
+            .collect();
+        let mut answers = solve_all(&sudokus).into_iter();
+
+        for (record, text) in chunk.drain(..) {
+            match record {
+                Some(Ok(record)) => match answers.next().expect("One answer per sudoku") {
+                    Ok(sudoku) => writeln!(output, "{}", sudoku.to_line_string())?,
+                    Err(Unsolved::NoSolution) => {
+                        problems.unsolvable.push(record.line);
+                        writeln!(output, "{}", failed)?;
+                    }
+                    Err(Unsolved::ManySolutions) => {
+                        problems.many_solutions.push(record.line);
+                        writeln!(output, "{}", failed)?;
+                    }
+                },
+                Some(Err(error)) => {
+                    problems.unreadable.push(error);
+                    writeln!(output, "{}", failed)?;
+                }
+                None => writeln!(output, "{}", text)?,
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solved(answer: &Answer) -> Sudoku {
+        answer.expect("Not solved")
+    }
+
+    #[test]
+    fn test_solve_batch_keeps_order() {
+        let sudokus = Sudoku::from_txt("test/solvable.txt".to_string());
+        let sudokus = &sudokus[..20];
+        let results = solve_batch(sudokus, 4);
+        assert_eq!(results.len(), sudokus.len());
+
+        // Same answers as solving them one at a time.
+        for (sudoku, result) in sudokus.iter().zip(results.iter()) {
+            let mut expected = *sudoku;
+            expected.solve(None);
+            assert!(expected.solved());
+            assert_eq!(solved(result).values(), expected.values());
+        }
+    }
+
+    #[test]
+    fn test_solve_all_lines() {
+        let sudokus = Sudoku::from_txt("test/top95.txt".to_string());
+        let results = solve_all(&sudokus[..10]);
+
+        for (sudoku, result) in sudokus.iter().zip(results.iter()) {
+            let before = sudoku.to_line_string();
+            let after = solved(result);
+            assert!(after.solved());
+
+            // Every given is still where it was.
+            for (b, a) in before.chars().zip(after.to_line_string().chars()) {
+                assert!(b == '.' || b == a);
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_stream() {
+        // Comments and blank lines come straight back out, so every line out is the
+        // line in solved.
+        let input = std::fs::read_to_string("test/top95.txt").unwrap();
+        let input: String = input
+            .lines()
+            .take(5)
+            .map(|l| format!("# comment\n{}\n\n", l))
+            .collect();
+
+        let mut output = Vec::new();
+        let problems = solve_stream(std::io::Cursor::new(input.clone()), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), input.lines().count());
+
+        let answers = solve_all(&Sudoku::from_txt("test/top95.txt".to_string())[..5]);
+        for (n, (line_in, line_out)) in input.lines().zip(output.lines()).enumerate() {
+            match n % 3 {
+                1 => assert_eq!(line_out, solved(&answers[n / 3]).to_line_string()),
+                _ => assert_eq!(line_out, line_in),
+            }
+        }
+        assert_eq!(problems, Problems::default());
+    }
+
+    #[test]
+    fn test_solve_stream_failures() {
+        // A line that won't read, one with no solution (r1c9 can't be anything) and
+        // one with lots still get a line each, so the rest stay in step with the
+        // input.
+        let top95 = std::fs::read_to_string("test/top95.txt").unwrap();
+        let lines: Vec<&str> = top95.lines().collect();
+        let unsolvable = format!("{}{}", "12345678.........9", ".".repeat(63));
+        let many = ".".repeat(81);
+        let input = format!(
+            "{}\n123\n{}\n{}\n{}\n",
+            lines[0], unsolvable, many, lines[1]
+        );
+
+        let mut output = Vec::new();
+        let problems = solve_stream(std::io::Cursor::new(input), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let output: Vec<&str> = output.lines().collect();
+
+        let failed = "x".repeat(81);
+        assert_eq!(output.len(), 5);
+        assert!(Sudoku::parse_line(output[0]).unwrap().solved());
+        assert_eq!(output[1..4], [failed.as_str(); 3]);
+        assert!(Sudoku::parse_line(output[4]).unwrap().solved());
+        assert_eq!(problems.unreadable.len(), 1);
+        assert_eq!(problems.unreadable[0].line, 2);
+        assert_eq!(problems.unsolvable, vec![3]);
+        assert_eq!(problems.many_solutions, vec![4]);
+    }
+}
@@ -0,0 +1,462 @@
+use crate::constants::*;
+use crate::forcing::Contradiction;
+use crate::houses::*;
+use crate::sk_box::*;
+use crate::sudoku::*;
+
+/*
+ * A compact version of the sudoku for when speed matters more than readability.
+ *
+ * The Sudoku/Cell/Box structure is nice to reason about, but working on it means
+ * converting between bool arrays and bitmaps and copying rows and columns around.
+ * Here every box is just its value and a u16 bitmap of candidates (bit n set means n
+ * is possible, the same layout as get_possibles_bits) held in row-major order, plus
+ * a bitmap per house of the values already placed in it.
+ *
+ * Only the work that gets repeated thousands of times per sudoku uses it: the
+ * propagation the forcing techniques try every branch with, and the search for
+ * solutions behind validate, batch and generate. The techniques in solvers.rs still
+ * work on the Sudoku itself, as that's what every step of a solve is shown as.
+ * benches/propagate.rs times the two against each other.
+ *
+ * Houses are numbered 0-8 for rows, 9-17 for columns and 18-26 for cells.
+ */
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct BitBoard {
+    // 0 if the box isn't solved, otherwise its value.
+    pub values: [u8; 81],
+    pub cands: [u16; 81],
+    pub placed: [u16; 27],
+}
+
+impl BitBoard {
+    /**
+     * from_sudoku
+     *
+     * Convert from the normal sudoku. Keeps everything, including boxes with a
+     * single candidate that haven't been marked as solved yet.
+     */
+    pub fn from_sudoku(sudoku: &Sudoku) -> BitBoard {
+        let mut board = BitBoard {
+            values: [0; 81],
+            cands: [0; 81],
+            placed: [0; 27],
+        };
+
+        for idx in 0..81 {
+            let (cell, box_idx) = CELL_BOX[idx];
+            let sk_box = sudoku.cells[cell].boxes[box_idx];
+
+            // Straight off the poss array rather than get_possibles_bits so even the
+            // unused 0 index comes across.
+            for n in 0..10 {
+                if sk_box.poss[n] {
+                    board.cands[idx] |= ON << n;
+                }
+            }
+
+            if let Some(value) = sk_box.value {
+                board.values[idx] = value;
+                for house in BOX_HOUSES[idx] {
+                    board.placed[house] |= ON << value;
+                }
+            }
+        }
+
+        board
+    }
+
+    /**
+     * to_sudoku
+     *
+     * Convert back to the normal sudoku, exactly undoing from_sudoku.
+     */
+    pub fn to_sudoku(self) -> Sudoku {
+        let mut sudoku = BLANK_SUDOKU;
+
+        for (idx, &(cell, box_idx)) in CELL_BOX.iter().enumerate() {
+            let sk_box = &mut sudoku.cells[cell].boxes[box_idx];
+
+            sk_box.value = match self.values[idx] {
+                0 => None,
+                value => Some(value),
+            };
+            for n in 0..10 {
+                sk_box.poss[n] = (self.cands[idx] >> n) & ON == ON;
+            }
+        }
+
+        sudoku
+    }
+
+    pub fn set_val(&mut self, idx: usize, value: u8) {
+        self.values[idx] = value;
+        self.cands[idx] = ON << value;
+        for house in BOX_HOUSES[idx] {
+            self.placed[house] |= ON << value;
+        }
+    }
+
+    pub fn is_poss(&self, idx: usize, value: u8) -> bool {
+        self.cands[idx] & (ON << value) != 0
+    }
+
+    /**
+     * contradiction
+     *
+     * Check every house for anything that shows the sudoku can't be solved.
+     */
+    pub fn contradiction(&self) -> Option<Contradiction> {
+        for house in 0..27 {
+            if let Some(reason) = self.house_contradiction(house) {
+                return Some(Contradiction { house, reason });
+            }
+        }
+        None
+    }
+
+    /**
+     * house_contradiction
+     *
+     * Check a house for anything that shows the sudoku can no longer be solved:
+     *  - A solved value turning up twice
+     *  - A box with no candidates left once solved values are taken out
+     *  - A group of values that only fit in fewer boxes than there are values
+     *    (which for a single value means it has nowhere to go)
+     */
+    fn house_contradiction(&self, house: usize) -> Option<&'static str> {
+        let indexes = HOUSES[house];
+
+        let mut solved_bits: u16 = 0;
+        for &idx in indexes.iter() {
+            let value = self.values[idx];
+            if value != 0 {
+                if solved_bits & (ON << value) != 0 {
+                    return Some("value repeated");
+                }
+                solved_bits |= ON << value;
+            }
+        }
+
+        // For each value a bitmap of which unsolved boxes it could go in, same as
+        // bitmap_possibles on a cell.
+        let mut places: [u16; 10] = [0; 10];
+        for (i, &idx) in indexes.iter().enumerate() {
+            if self.values[idx] != 0 {
+                continue;
+            }
+            let poss = self.cands[idx] & !solved_bits & POSSIBLE_BIT_MASK;
+            if poss == 0 {
+                return Some("box has no candidates");
+            }
+            for (value, place) in places.iter_mut().enumerate().skip(1) {
+                if poss & (ON << value) != 0 {
+                    *place |= ON << i;
+                }
+            }
+        }
+
+        // Now check every group of unsolved values fits into at least as many boxes as
+        // there are values. Groups are bitmaps over the unsolved values (bit 0 is the
+        // first unsolved value and so on) and each group's boxes are the boxes of the
+        // group without its lowest value, plus the boxes of that value.
+        let mut unsolved = [0; 9];
+        let mut len = 0;
+        for value in 1..=9 {
+            if solved_bits & (ON << value) == 0 {
+                unsolved[len] = value;
+                len += 1;
+            }
+        }
+        let mut boxes_used = [0u16; 512];
+        for group in 1..(1usize << len) {
+            let lowest = group.trailing_zeros() as usize;
+            boxes_used[group] = boxes_used[group & (group - 1)] | places[unsolved[lowest]];
+            if boxes_used[group].count_ones() < group.count_ones() {
+                return Some(if group.count_ones() == 1 {
+                    "value has nowhere to go"
+                } else {
+                    "values do not fit in the boxes left"
+                });
+            }
+        }
+
+        None
+    }
+
+    /**
+     * single_position_house
+     *
+     * Same as single_position_boxes. Take solved values out of the other boxes in the
+     * house, solve any box left with one candidate, and solve any value that only has
+     * one box left it could go in.
+     */
+    fn single_position_house(&mut self, house: usize) {
+        let indexes = HOUSES[house];
+        let solved_bits = self.placed_bits(&indexes);
+
+        // Which values turn up in one box, and which in more than one.
+        let mut once: u16 = 0;
+        let mut many: u16 = 0;
+        for &idx in indexes.iter() {
+            if self.values[idx] != 0 {
+                continue;
+            }
+
+            let cands = self.cands[idx] & !solved_bits;
+            self.cands[idx] = cands;
+            if cands.count_ones() == 1 {
+                self.set_val(idx, cands.trailing_zeros() as u8);
+            }
+
+            many |= once & cands;
+            once |= cands;
+        }
+
+        let singles = once & !many & POSSIBLE_BIT_MASK;
+        for value in 1..=9 {
+            if singles & (ON << value) != 0 {
+                for &idx in indexes.iter() {
+                    if self.values[idx] == 0 && self.is_poss(idx, value) {
+                        self.set_val(idx, value);
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+     * naked_set_house
+     *
+     * Same as naked_set_boxes. For groups of 2, 3 and 4 unsolved values, if they
+     * only turn up in as many boxes as there are values, then those boxes can't be
+     * anything else.
+     */
+    fn naked_set_house(&mut self, house: usize) {
+        let indexes = HOUSES[house];
+        let unsolved = Box::invert_possible_bits(self.placed_bits(&indexes));
+
+        // Walk every subset of the unsolved values, skipping the ones too big or small.
+        let mut pattern = unsolved;
+        while pattern != 0 {
+            let factors = pattern.count_ones();
+            if (2..=4).contains(&factors) {
+                let mut matched: u16 = 0;
+                for (i, &idx) in indexes.iter().enumerate() {
+                    if self.cands[idx] & pattern != 0 {
+                        matched |= ON << i;
+                    }
+                }
+
+                if matched.count_ones() == factors {
+                    for (i, &idx) in indexes.iter().enumerate() {
+                        if matched & (ON << i) != 0 {
+                            let cands = self.cands[idx] & pattern;
+                            self.cands[idx] = cands;
+                            if self.values[idx] == 0 && cands.count_ones() == 1 {
+                                self.set_val(idx, cands.trailing_zeros() as u8);
+                            }
+                        }
+                    }
+                }
+            }
+            pattern = (pattern - 1) & unsolved;
+        }
+    }
+
+    // Bitmap of the values solved in a house
+    fn placed_bits(&self, indexes: &[usize; 9]) -> u16 {
+        let mut result = 0;
+        for &idx in indexes.iter() {
+            if self.values[idx] != 0 {
+                result |= ON << self.values[idx];
+            }
+        }
+        result
+    }
+
+    /**
+     * propagate
+     *
+     * Run the single position and naked set logic over every house until nothing
+     * changes, giving back where it broke if the board turns out to be unsolvable.
+     *
+     * Unlike the Sudoku solvers nothing in here asserts on a broken board, so it's
+     * enough to check once a round. Candidates only ever get removed so anything
+     * broken part way through a round is still broken at the end of it.
+     */
+    pub fn propagate(&mut self) -> Result<(), Contradiction> {
+        loop {
+            let before = self.cands;
+
+            for house in 0..27 {
+                self.single_position_house(house);
+            }
+
+            for house in 0..27 {
+                self.naked_set_house(house);
+            }
+
+            if let Some(contradiction) = self.contradiction() {
+                return Err(contradiction);
+            }
+
+            if before == self.cands {
+                return Ok(());
+            }
+        }
+    }
+
+    /**
+     * count_solutions
+     *
+     * Count how many ways the board can be finished, giving up once limit is reached
+     * as usually all we care about is whether there's one or more than one.
+     */
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        self.solutions(limit).len()
+    }
+
+    /**
+     * solutions
+     *
+     * The finished boards themselves, up to limit of them. Each step propagates as
+     * far as it can, then tries every candidate of whichever unsolved box has the
+     * fewest.
+     */
+    pub fn solutions(&self, limit: usize) -> Vec<BitBoard> {
+        let mut found = Vec::new();
+        self.find_solutions(limit, &mut found);
+        found
+    }
+
+    fn find_solutions(&self, limit: usize, found: &mut Vec<BitBoard>) {
+        let mut board = *self;
+        if board.propagate().is_err() {
+            return;
+        }
+
+        let mut best: Option<usize> = None;
+        for idx in 0..81 {
+            if board.values[idx] == 0 {
+                let fewer = match best {
+                    Some(best_idx) => {
+                        board.cands[idx].count_ones() < board.cands[best_idx].count_ones()
+                    }
+                    None => true,
+                };
+                if fewer {
+                    best = Some(idx);
+                }
+            }
+        }
+
+        let idx = match best {
+            Some(idx) => idx,
+            // Nothing left to solve and nothing broken, so that's one.
+            None => {
+                found.push(board);
+                return;
+            }
+        };
+
+        for value in 1..=9 {
+            if found.len() < limit && board.is_poss(idx, value) {
+                let mut branch = board;
+                branch.set_val(idx, value);
+                branch.find_solutions(limit, found);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solvers;
+
+    #[test]
+    fn test_round_trip() {
+        for filename in [
+            "test/blank.ss",
+            "test/simple.ss",
+            "test/sparse.ss",
+            "test/solved.ss",
+        ] {
+            let sudoku = Sudoku::from_ss(filename.to_string()).unwrap();
+            assert_eq!(BitBoard::from_sudoku(&sudoku).to_sudoku(), sudoku);
+        }
+
+        // Boxes down to one candidate without being solved have to survive too.
+        let sudoku = Sudoku::from_possibles("test/xwing_ready.ss".to_string());
+        assert_eq!(BitBoard::from_sudoku(&sudoku).to_sudoku(), sudoku);
+
+        let mut sudoku = BLANK_SUDOKU;
+        sudoku.cells[MID_MID].boxes[MID_MID].poss = [false; 10];
+        sudoku.cells[MID_MID].boxes[MID_MID].poss[4] = true;
+        let board = BitBoard::from_sudoku(&sudoku);
+        assert_eq!(board.values[40], 0);
+        assert_eq!(board.cands[40], ON << 4);
+        assert_eq!(board.to_sudoku(), sudoku);
+    }
+
+    #[test]
+    fn test_placed() {
+        let sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        let board = BitBoard::from_sudoku(&sudoku);
+
+        // Top row is ...|26.|7.1
+        assert_eq!(board.placed[0], ON << 1 | ON << 2 | ON << 6 | ON << 7);
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        let sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        assert_eq!(BitBoard::from_sudoku(&sudoku).count_solutions(2), 1);
+
+        let sudoku = Sudoku::from_ss("test/blank.ss".to_string()).unwrap();
+        assert_eq!(BitBoard::from_sudoku(&sudoku).count_solutions(5), 5);
+
+        // Top95 are all proper sudokus with just the one answer.
+        for sudoku in Sudoku::from_txt("test/top95.txt".to_string())
+            .iter()
+            .take(10)
+        {
+            assert_eq!(BitBoard::from_sudoku(sudoku).count_solutions(2), 1);
+        }
+
+        // Put in a value that clashes with the row and there's no answer at all.
+        let sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        let mut board = BitBoard::from_sudoku(&sudoku);
+        board.set_val(0, 1);
+        assert_eq!(board.count_solutions(2), 0);
+    }
+
+    // The old way of doing the same thing - run the Sudoku based solvers until they stop.
+    fn propagate_sudoku(sudoku: &mut Sudoku) {
+        loop {
+            let before = *sudoku;
+            solvers::single_position(sudoku);
+            solvers::naked_set(sudoku);
+            if before == *sudoku {
+                return;
+            }
+        }
+    }
+
+    #[test]
+    fn test_propagate_matches_solvers() {
+        for sudoku in Sudoku::from_txt("test/top95.txt".to_string())
+            .iter()
+            .take(10)
+        {
+            let mut expected = *sudoku;
+            propagate_sudoku(&mut expected);
+
+            let mut board = BitBoard::from_sudoku(sudoku);
+            board.propagate().unwrap();
+            assert_eq!(board.to_sudoku(), expected);
+        }
+    }
+}
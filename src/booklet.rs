@@ -0,0 +1,396 @@
+/*
+ * Printable booklets of puzzles, a few to a page with a title and how hard each
+ * one is, and the answers all at the back.
+ *
+ * It can be one HTML file with the pages split up for printing, or a separate SVG
+ * file for each A4 page.
+ */
+use crate::bitboard::BitBoard;
+use crate::rating::{self, Technique};
+use crate::sudoku::Sudoku;
+use crate::svg;
+use std::fmt::Write;
+
+// An A4 page at 96 pixels an inch, and the gap left around the edge.
+const PAGE_WIDTH: usize = 794;
+const PAGE_HEIGHT: usize = 1123;
+const PAGE_MARGIN: usize = 48;
+const HEADING_HEIGHT: usize = 48;
+const LABEL_HEIGHT: usize = 28;
+
+// Any more and the answer pages, at twice as many a page, get too small to read.
+pub const MAX_PER_PAGE: usize = 12;
+
+const HTML_STYLE: &str = "
+@page { size: A4; margin: 12mm; }
+body { font-family: sans-serif; margin: 0; }
+.page { page-break-after: always; break-after: page; }
+.page:last-child { page-break-after: auto; break-after: auto; }
+.page h1 { font-size: 20pt; text-align: center; margin: 0 0 6mm 0; }
+.puzzles { display: grid; gap: 8mm; }
+.puzzle { break-inside: avoid; text-align: center; }
+.puzzle h2 { font-size: 12pt; margin: 0 0 2mm 0; }
+.puzzle svg { width: 100%; height: auto; }
+";
+
+/**
+ * Entry
+ *
+ * A puzzle in the booklet, with its answer (or as far as the solver got) and a
+ * label saying how hard it is.
+ */
+#[derive(Clone)]
+pub struct Entry {
+    pub puzzle: Sudoku,
+    pub answer: Sudoku,
+    pub difficulty: String,
+}
+
+/**
+ * Booklet
+ *
+ * Everything needed to lay the pages out. Answers are printed twice as many to a
+ * page as the puzzles as they don't need room for working.
+ */
+pub struct Booklet {
+    pub title: String,
+    pub per_page: usize,
+    pub entries: Vec<Entry>,
+}
+
+/**
+ * difficulty
+ *
+ * A word for how hard a puzzle is from the hardest technique it needs.
+ */
+pub fn difficulty(hardest: Technique) -> &'static str {
+    match hardest {
+        Technique::SinglePosition => "Easy",
+        Technique::NakedSet | Technique::CandidateLine => "Medium",
+        Technique::XWing => "Hard",
+        Technique::DigitNishio | Technique::CellForcingChain | Technique::UnitForcingChain => {
+            "Fiendish"
+        }
+    }
+}
+
+impl Booklet {
+    /**
+     * new
+     *
+     * Solve and rate every puzzle ready to print. Puzzles with no answer at all get
+     * printed as they are, labelled Unsolvable, as the solver can't go near them.
+     */
+    pub fn new(title: &str, per_page: usize, puzzles: &[Sudoku]) -> Booklet {
+        assert!(
+            (1..=MAX_PER_PAGE).contains(&per_page),
+            "Need between 1 and {} puzzles a page",
+            MAX_PER_PAGE
+        );
+        let entries = puzzles
+            .iter()
+            .map(|puzzle| {
+                if BitBoard::from_sudoku(puzzle).count_solutions(1) == 0 {
+                    return Entry {
+                        puzzle: *puzzle,
+                        answer: *puzzle,
+                        difficulty: "Unsolvable".to_string(),
+                    };
+                }
+                let trace = rating::trace(puzzle);
+                let hardest = trace.steps.iter().map(|step| step.technique).max();
+                let difficulty = if !trace.solved {
+                    "Unsolved".to_string()
+                } else if let Some(hardest) = hardest {
+                    format!("{} ({})", difficulty(hardest), hardest)
+                } else {
+                    // Nothing to do at all.
+                    difficulty(Technique::SinglePosition).to_string()
+                };
+                Entry {
+                    puzzle: *puzzle,
+                    answer: trace.result,
+                    difficulty,
+                }
+            })
+            .collect();
+
+        Booklet {
+            title: title.to_string(),
+            per_page,
+            entries,
+        }
+    }
+
+    // Each page as its heading and the grids on it, each with its title, label and
+    // what to draw.
+    fn pages(&self) -> Vec<Page> {
+        let mut pages = Vec::new();
+        for (first, chunk) in (0..self.entries.len())
+            .step_by(self.per_page)
+            .zip(self.entries.chunks(self.per_page))
+        {
+            pages.push(Page {
+                heading: self.title.clone(),
+                per_page: self.per_page,
+                grids: chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, entry)| Grid {
+                        title: format!("Puzzle {}", first + idx + 1),
+                        label: entry.difficulty.clone(),
+                        sudoku: entry.puzzle,
+                        givens: None,
+                    })
+                    .collect(),
+            });
+        }
+
+        let per_page = self.per_page * 2;
+        for (first, chunk) in (0..self.entries.len())
+            .step_by(per_page)
+            .zip(self.entries.chunks(per_page))
+        {
+            pages.push(Page {
+                heading: "Answers".to_string(),
+                per_page,
+                grids: chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, entry)| Grid {
+                        title: format!("Answer {}", first + idx + 1),
+                        label: String::new(),
+                        sudoku: entry.answer,
+                        givens: Some(entry.puzzle),
+                    })
+                    .collect(),
+            });
+        }
+        pages
+    }
+
+    /**
+     * to_html
+     *
+     * The whole booklet as one HTML file with everything it needs inside it, that
+     * breaks onto a new page at the start of each page when printed.
+     */
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        writeln!(html, "<!DOCTYPE html>").unwrap();
+        writeln!(html, "<html>\n<head>\n<meta charset=\"utf-8\">").unwrap();
+        writeln!(html, "<title>{}</title>", escape(&self.title)).unwrap();
+        writeln!(html, "<style>{}</style>\n</head>\n<body>", HTML_STYLE).unwrap();
+
+        for page in self.pages() {
+            writeln!(html, "<section class=\"page\">").unwrap();
+            writeln!(html, "<h1>{}</h1>", escape(&page.heading)).unwrap();
+            writeln!(
+                html,
+                "<div class=\"puzzles\" style=\"grid-template-columns: repeat({}, 1fr);\">",
+                page.columns()
+            )
+            .unwrap();
+            for grid in page.grids.iter() {
+                writeln!(html, "<div class=\"puzzle\">").unwrap();
+                writeln!(html, "<h2>{}</h2>", grid.heading()).unwrap();
+                html.push_str(&svg::render(&grid.sudoku, &grid.options()));
+                writeln!(html, "</div>").unwrap();
+            }
+            writeln!(html, "</div>\n</section>").unwrap();
+        }
+
+        writeln!(html, "</body>\n</html>").unwrap();
+        html
+    }
+
+    /**
+     * to_svg_pages
+     *
+     * The booklet as a standalone SVG for each A4 page, in order.
+     */
+    pub fn to_svg_pages(&self) -> Vec<String> {
+        self.pages().iter().map(|page| page.to_svg()).collect()
+    }
+}
+
+struct Page {
+    heading: String,
+    per_page: usize,
+    grids: Vec<Grid>,
+}
+
+struct Grid {
+    title: String,
+    label: String,
+    sudoku: Sudoku,
+    givens: Option<Sudoku>,
+}
+
+impl Grid {
+    fn heading(&self) -> String {
+        if self.label.is_empty() {
+            escape(&self.title)
+        } else {
+            format!("{} - {}", escape(&self.title), escape(&self.label))
+        }
+    }
+
+    fn options(&self) -> svg::SvgOptions {
+        svg::SvgOptions {
+            givens: self.givens,
+            ..svg::SvgOptions::default()
+        }
+    }
+}
+
+impl Page {
+    // Tall pages fit two grids one above the other, then two across.
+    fn columns(&self) -> usize {
+        match self.per_page {
+            1 | 2 => 1,
+            3..=8 => 2,
+            _ => 3,
+        }
+    }
+
+    fn to_svg(&self) -> String {
+        let columns = self.columns();
+        let rows = self.per_page.div_ceil(columns);
+        let slot_width = (PAGE_WIDTH - PAGE_MARGIN * 2) / columns;
+        let slot_height = (PAGE_HEIGHT - PAGE_MARGIN * 2 - HEADING_HEIGHT) / rows;
+
+        // Scale the grids to fit the smaller of the width and the height left once
+        // the label is on, with a bit of room between them.
+        let grid_size = svg::BOX_SIZE * 9;
+        let room = (slot_width.min(slot_height.saturating_sub(LABEL_HEIGHT)) as f64) * 0.9;
+        let scale = room / grid_size as f64;
+
+        let mut page = String::new();
+        writeln!(
+            page,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+            PAGE_WIDTH, PAGE_HEIGHT
+        )
+        .unwrap();
+        writeln!(
+            page,
+            "<style>{}.heading {{ font: bold 26px sans-serif; }}\n.label {{ font: bold 15px sans-serif; }}\n</style>",
+            svg::STYLE
+        )
+        .unwrap();
+        writeln!(
+            page,
+            "<rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"white\"/>",
+            PAGE_WIDTH, PAGE_HEIGHT
+        )
+        .unwrap();
+        writeln!(
+            page,
+            "<text class=\"heading\" x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            PAGE_WIDTH / 2,
+            PAGE_MARGIN + HEADING_HEIGHT / 2,
+            escape(&self.heading)
+        )
+        .unwrap();
+
+        for (idx, grid) in self.grids.iter().enumerate() {
+            let left = PAGE_MARGIN + (idx % columns) * slot_width;
+            let top = PAGE_MARGIN + HEADING_HEIGHT + (idx / columns) * slot_height;
+            let centre = left as f64 + slot_width as f64 / 2.0;
+
+            writeln!(
+                page,
+                "<text class=\"label\" x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+                centre,
+                top + LABEL_HEIGHT * 2 / 3,
+                grid.heading()
+            )
+            .unwrap();
+            writeln!(
+                page,
+                "<g transform=\"translate({:.1} {}) scale({:.3})\">",
+                centre - room / 2.0,
+                top + LABEL_HEIGHT,
+                scale
+            )
+            .unwrap();
+            page.push_str(&svg::render_grid(&grid.sudoku, &grid.options(), 0, 0));
+            writeln!(page, "</g>").unwrap();
+        }
+
+        page.push_str("</svg>\n");
+        page
+    }
+}
+
+// Make text safe to put in HTML or SVG.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn booklet(count: usize, per_page: usize) -> Booklet {
+        let puzzles = Sudoku::from_txt("test/solvable.txt".to_string());
+        Booklet::new("Week <1>", per_page, &puzzles[..count])
+    }
+
+    #[test]
+    fn test_booklet_html() {
+        let html = booklet(5, 2).to_html();
+
+        // 3 pages of puzzles and 2 of answers.
+        assert_eq!(html.matches("<section class=\"page\">").count(), 5);
+        assert_eq!(html.matches("<h1>Week &lt;1&gt;</h1>").count(), 3);
+        assert_eq!(html.matches("<h1>Answers</h1>").count(), 2);
+        assert_eq!(html.matches("<svg").count(), 10);
+        for number in 1..=5 {
+            assert!(html.contains(&format!("<h2>Puzzle {} - ", number)));
+            assert!(html.contains(&format!("<h2>Answer {}</h2>", number)));
+        }
+        assert!(html.ends_with("</html>\n"));
+    }
+
+    #[test]
+    fn test_booklet_svg_pages() {
+        let pages = booklet(7, 4).to_svg_pages();
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[0].matches("<g transform").count(), 4 * 2);
+        assert_eq!(pages[1].matches("<g transform").count(), 3 * 2);
+        assert_eq!(pages[2].matches("<g transform").count(), 7 * 2);
+        assert!(pages.iter().all(|page| page.ends_with("</svg>\n")));
+
+        // As many as fit still leave room for the grids.
+        let full = booklet(MAX_PER_PAGE * 2, MAX_PER_PAGE).to_svg_pages();
+        assert_eq!(full.len(), 3);
+        assert!(!full[2].contains("scale(0.000)"));
+
+        // The answers keep the givens picked out.
+        assert!(pages[2].contains("class=\"solved\""));
+        assert!(!pages[0].contains("class=\"solved\""));
+    }
+
+    #[test]
+    fn test_difficulty() {
+        let puzzle = Sudoku::from_ss("test/xwing.ss".to_string()).unwrap();
+        let booklet = Booklet::new("X Wings", 1, &[puzzle]);
+        assert_eq!(booklet.entries[0].difficulty, "Hard (X Wing)");
+        assert!(booklet.entries[0].answer.solved());
+
+        // r1c9 can't be anything, so there's nothing to solve.
+        let line = format!("{}{}", "12345678.........9", ".".repeat(63));
+        let unsolvable = Sudoku::parse_line(&line).unwrap();
+        let booklet = Booklet::new("Broken", 1, &[unsolvable]);
+        assert_eq!(booklet.entries[0].difficulty, "Unsolvable");
+        assert_eq!(booklet.entries[0].answer, unsolvable);
+
+        assert_eq!(difficulty(Technique::XWing), "Hard");
+        assert_eq!(difficulty(Technique::UnitForcingChain), "Fiendish");
+    }
+}
@@ -0,0 +1,210 @@
+/*
+ * Candidate grids on one line, for swapping half solved sudokus with other tools
+ * without the box drawing characters of print_possibles getting mangled on the way.
+ *
+ * There are two common ways of doing it. 729 characters, 9 for each box in row-major
+ * order with the digit if it's still possible and a '.' (or '0') if not:
+ *
+ * 1.......9.2.4.6...
+ *
+ * Or the possible values of each box run together and spaced apart:
+ *
+ * 19 246 3 ...
+ *
+ * Either way a box with just one possible value is taken as solved, as there's no
+ * way to tell the two apart.
+ */
+use crate::reader::*;
+use crate::sk_box::*;
+use crate::sudoku::*;
+use std::io::BufRead;
+
+impl Sudoku {
+    /**
+     * parse_candidates
+     *
+     * Read a sudoku from a line in either candidate format, going by whether it has
+     * spaces in it. Unlike parse_line the possible values are kept as they are and
+     * not worked out again, as that's the whole point.
+     */
+    pub fn parse_candidates(input: &str) -> Result<Sudoku, &'static str> {
+        let input = input.trim();
+        let mut result = BLANK_SUDOKU;
+
+        if input.contains(char::is_whitespace) {
+            let boxes: Vec<&str> = input.split_whitespace().collect();
+            if boxes.len() != 81 {
+                return Err("expected 81 boxes");
+            }
+            for (idx, possibles) in boxes.iter().enumerate() {
+                let mut bits = 0;
+                for c in possibles.chars() {
+                    match c {
+                        '1'..='9' => bits |= 1 << (c as u8 - b'0'),
+                        _ => return Err("expected possible values between 1 and 9"),
+                    }
+                }
+                if bits == 0 {
+                    return Err("box with no possible values");
+                }
+                *result.box_at_mut(idx) = Box::from_possibles_bits(bits);
+            }
+        } else {
+            let chars: Vec<char> = input.chars().collect();
+            if chars.len() != 729 {
+                return Err("expected 729 candidates");
+            }
+            for (idx, possibles) in chars.chunks(9).enumerate() {
+                let mut bits = 0;
+                for (value, &c) in (1..=9).zip(possibles.iter()) {
+                    if c == (b'0' + value) as char {
+                        bits |= 1 << value;
+                    } else if c != '.' && c != '0' {
+                        return Err("expected each value in its own place or a '.'");
+                    }
+                }
+                if bits == 0 {
+                    return Err("box with no possible values");
+                }
+                *result.box_at_mut(idx) = Box::from_possibles_bits(bits);
+            }
+        }
+
+        if result.repeated_value() {
+            return Err("value repeated");
+        }
+
+        Ok(result)
+    }
+
+    /**
+     * to_candidates_string / to_candidate_list_string
+     *
+     * Write the 729 character and the spaced out formats, which parse_candidates
+     * reads back.
+     */
+    pub fn to_candidates_string(self) -> String {
+        let mut result = String::with_capacity(729);
+        for idx in 0..81 {
+            let sk_box = self.box_at(idx);
+            for value in 1..=9 {
+                if sk_box.is_poss(value) {
+                    result.push((b'0' + value) as char);
+                } else {
+                    result.push('.');
+                }
+            }
+        }
+        result
+    }
+
+    #[cfg(test)]
+    pub fn to_candidate_list_string(self) -> String {
+        let mut boxes = Vec::with_capacity(81);
+        for idx in 0..81 {
+            let possibles: String = self
+                .box_at(idx)
+                .get_possibles()
+                .iter()
+                .map(|&value| (b'0' + value as u8) as char)
+                .collect();
+            boxes.push(possibles);
+        }
+        boxes.join(" ")
+    }
+
+    /**
+     * from_candidates / read_candidates
+     *
+     * Read a file, or text already read in, with a sudoku per line in either
+     * candidate format. Lines are read by RecordReader so blank lines and '#'
+     * comments are skipped the same as in any other file of a sudoku a line, and a
+     * file is read a line at a time.
+     */
+    #[cfg(test)]
+    pub fn from_candidates(filename: String) -> Result<Vec<Sudoku>, ParseError> {
+        let file = std::fs::File::open(filename).map_err(|error| ParseError {
+            line: 0,
+            reason: error.to_string(),
+        })?;
+        candidate_records(std::io::BufReader::new(file))
+    }
+
+    pub fn read_candidates(text: &str) -> Result<Vec<Sudoku>, ParseError> {
+        candidate_records(text.as_bytes())
+    }
+}
+
+// Every sudoku in lines of candidates, or the first line that's wrong.
+fn candidate_records<R: BufRead>(reader: R) -> Result<Vec<Sudoku>, ParseError> {
+    let parse: LineParser = |line| Sudoku::parse_candidates(line).map(|sudoku| (sudoku, None));
+    RecordReader::with_parser(reader, parse)
+        .map(|record| record.map(|record| record.sudoku))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_round_trip() {
+        for filename in ["test/xwing_ready.ss", "test/possibles.txt"] {
+            let sudoku = Sudoku::from_possibles(filename.to_string());
+
+            let line = sudoku.to_candidates_string();
+            assert_eq!(line.len(), 729);
+            assert_eq!(Sudoku::parse_candidates(&line).unwrap(), sudoku);
+
+            let list = sudoku.to_candidate_list_string();
+            assert_eq!(list.split(' ').count(), 81);
+            assert_eq!(Sudoku::parse_candidates(&list).unwrap(), sudoku);
+        }
+    }
+
+    #[test]
+    fn test_parse_candidates() {
+        // The same blank sudoku both ways, with 0s instead of dots for good measure.
+        let blank = "123456789 ".repeat(81);
+        assert_eq!(Sudoku::parse_candidates(&blank).unwrap(), BLANK_SUDOKU);
+        let blank = "123456789".repeat(81);
+        assert_eq!(Sudoku::parse_candidates(&blank).unwrap(), BLANK_SUDOKU);
+
+        // One possible value means solved.
+        let mut line = "123456789".repeat(81);
+        line.replace_range(0..9, "0000500..");
+        let sudoku = Sudoku::parse_candidates(&line).unwrap();
+        assert_eq!(sudoku.box_at(0).value, Some(5));
+        assert_eq!(sudoku.box_at(1).value, None);
+
+        assert_eq!(
+            Sudoku::parse_candidates(&line[9..]),
+            Err("expected 729 candidates")
+        );
+        line.replace_range(9..18, "2........");
+        assert_eq!(
+            Sudoku::parse_candidates(&line),
+            Err("expected each value in its own place or a '.'")
+        );
+        line.replace_range(9..18, ".........");
+        assert_eq!(
+            Sudoku::parse_candidates(&line),
+            Err("box with no possible values")
+        );
+        line.replace_range(9..18, "....5....");
+        assert_eq!(Sudoku::parse_candidates(&line), Err("value repeated"));
+    }
+
+    #[test]
+    fn test_read_candidates() {
+        let sudokus = Sudoku::from_candidates("test/xwing_ready.cand".to_string()).unwrap();
+        let expected = Sudoku::from_possibles("test/xwing_ready.ss".to_string());
+        assert_eq!(sudokus, vec![expected, expected]);
+
+        // Comments are skipped however they're indented, and errors say which line.
+        let line = expected.to_candidates_string();
+        let text = format!("  # note\n{}\n\n{}x\n", line, line);
+        let error = Sudoku::read_candidates(&text).unwrap_err();
+        assert_eq!(error.to_string(), "line 4: expected 729 candidates");
+    }
+}
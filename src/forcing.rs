@@ -0,0 +1,529 @@
+use crate::bitboard::*;
+use crate::constants::*;
+use crate::houses::*;
+use crate::sudoku::Sudoku;
+use std::fmt;
+
+/*
+ * Contradiction based solving techniques. These are the last thing we try before
+ * giving up on a sudoku, and work by "trying" each candidate of a box (or each
+ * position of a value in a row/col/cell), running the simple solvers over the
+ * result and seeing what happens.
+ *
+ * If a branch falls over then that candidate can't be right and gets removed. If
+ * every branch ends up agreeing on something (a box's value, or a value not being
+ * possible somewhere) then that must be true no matter which branch is right.
+ *
+ * None of this is guessing - every step we report has a reason that can be
+ * checked by hand, it's just a long reason.
+ */
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum ForcingTechnique {
+    DigitNishio,
+    CellForcingChain,
+    UnitForcingChain,
+}
+
+impl fmt::Display for ForcingTechnique {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ForcingTechnique::DigitNishio => formatter.write_str("Digit Nishio"),
+            ForcingTechnique::CellForcingChain => formatter.write_str("Cell Forcing Chain"),
+            ForcingTechnique::UnitForcingChain => formatter.write_str("Unit Forcing Chain"),
+        }
+    }
+}
+
+/**
+ * Contradiction
+ *
+ * Says where and why a branch fell over. Houses are numbered 0-26, rows first,
+ * then columns, then cells.
+ */
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Contradiction {
+    pub house: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} {}: {}",
+            house_name(self.house),
+            (self.house % 9) + 1,
+            self.reason
+        )
+    }
+}
+
+/**
+ * Branch
+ *
+ * One of the assumptions tried by a forcing technique. Column and row are
+ * from 1 to 9 like the rest of the sudoku accessors.
+ */
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Branch {
+    pub col: usize,
+    pub row: usize,
+    pub value: u8,
+    pub contradiction: Option<Contradiction>,
+}
+
+/**
+ * ForcingStep
+ *
+ * A deduction found by one of the techniques in here, along with the branches
+ * that got tried so it can be explained.
+ *
+ * placements and eliminations are (col, row, value) with col and row from 1 to 9.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub struct ForcingStep {
+    pub technique: ForcingTechnique,
+    pub branches: Vec<Branch>,
+    pub placements: Vec<(usize, usize, u8)>,
+    pub eliminations: Vec<(usize, usize, u8)>,
+}
+
+impl ForcingStep {
+    /**
+     * apply
+     *
+     * Make the changes this step found onto a sudoku. Doesn't tidy up the other
+     * boxes after placing values, the normal solvers will do that next round.
+     */
+    pub fn apply(&self, sudoku: &mut Sudoku) {
+        for &(col, row, value) in self.placements.iter() {
+            let (cell, idx) = Sudoku::col_row_to_cell_idx(col, row);
+            sudoku.cells[cell].boxes[idx].set_val(value);
+        }
+
+        for &(col, row, value) in self.eliminations.iter() {
+            let (cell, idx) = Sudoku::col_row_to_cell_idx(col, row);
+            sudoku.cells[cell].boxes[idx].remove_possible_value(value as u16);
+        }
+    }
+}
+
+impl fmt::Display for ForcingStep {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}:", self.technique)?;
+        for branch in self.branches.iter() {
+            write!(
+                formatter,
+                " r{}c{}={}",
+                branch.row, branch.col, branch.value
+            )?;
+            match branch.contradiction {
+                Some(contradiction) => write!(formatter, " fails ({});", contradiction)?,
+                None => write!(formatter, " holds;")?,
+            }
+        }
+        for &(col, row, value) in self.placements.iter() {
+            write!(formatter, " place r{}c{}={}", row, col, value)?;
+        }
+        for &(col, row, value) in self.eliminations.iter() {
+            write!(formatter, " remove {} from r{}c{}", value, row, col)?;
+        }
+        Ok(())
+    }
+}
+
+fn house_name(house: usize) -> &'static str {
+    match house / 9 {
+        0 => "row",
+        1 => "column",
+        _ => "cell",
+    }
+}
+
+// Row-major index to the col/row pair used by the rest of the sudoku, both 1 to 9.
+fn col_row(idx: usize) -> (usize, usize) {
+    (idx % 9 + 1, idx / 9 + 1)
+}
+
+/**
+ * propagate
+ *
+ * Run single_position and naked_set over the sudoku until they stop changing
+ * anything. Unlike solving normally this never panics on a broken sudoku, as
+ * each house is checked before the solvers are let loose on it, and returns
+ * where it broke instead.
+ *
+ * The work is done on a BitBoard as the forcing techniques call this a lot.
+ */
+#[cfg(test)]
+fn propagate(sudoku: &mut Sudoku) -> Result<(), Contradiction> {
+    let mut board = BitBoard::from_sudoku(sudoku);
+    let result = board.propagate();
+    *sudoku = board.to_sudoku();
+    result
+}
+
+/**
+ * try_branch
+ *
+ * Copy the board, set a value, and see how far propagate gets with it.
+ */
+fn try_branch(board: &BitBoard, idx: usize, value: u8) -> Result<BitBoard, Contradiction> {
+    let mut branch = *board;
+    branch.set_val(idx, value);
+    branch.propagate()?;
+    Ok(branch)
+}
+
+/**
+ * forcing_branches
+ *
+ * The common part of both forcing chains. Given a list of (box, value) assumptions,
+ * at least one of which must be true, try them all and work out what we learn.
+ *
+ * Any branch that fails means that assumption is false, so the value is removed
+ * from that box. If none fail, anything that all the branches agree on is true.
+ */
+fn forcing_branches(
+    board: &BitBoard,
+    technique: ForcingTechnique,
+    assumptions: &[(usize, u8)],
+) -> Option<ForcingStep> {
+    let mut branches = Vec::new();
+    let mut results = Vec::new();
+
+    for &(idx, value) in assumptions.iter() {
+        let (col, row) = col_row(idx);
+        let result = try_branch(board, idx, value);
+        branches.push(Branch {
+            col,
+            row,
+            value,
+            contradiction: result.err(),
+        });
+        if let Ok(branch) = result {
+            results.push(branch);
+        }
+    }
+
+    // If everything fails the sudoku was broken before we started, and there's
+    // nothing sensible to report.
+    if results.is_empty() {
+        return None;
+    }
+
+    let mut step = ForcingStep {
+        technique,
+        branches,
+        placements: Vec::new(),
+        eliminations: Vec::new(),
+    };
+
+    if results.len() < assumptions.len() {
+        step.eliminations = step
+            .branches
+            .iter()
+            .filter(|branch| branch.contradiction.is_some())
+            .map(|branch| (branch.col, branch.row, branch.value))
+            .collect();
+        return Some(step);
+    }
+
+    for idx in 0..81 {
+        if board.values[idx] != 0 {
+            continue;
+        }
+        let (col, row) = col_row(idx);
+
+        // Every branch solved this box to the same value.
+        let value = results[0].values[idx];
+        if value != 0 && results.iter().all(|branch| branch.values[idx] == value) {
+            step.placements.push((col, row, value));
+            continue;
+        }
+
+        // Every branch removed this candidate.
+        for value in 1..=9 {
+            if board.is_poss(idx, value) && results.iter().all(|branch| !branch.is_poss(idx, value))
+            {
+                step.eliminations.push((col, row, value));
+            }
+        }
+    }
+
+    if step.placements.is_empty() && step.eliminations.is_empty() {
+        None
+    } else {
+        Some(step)
+    }
+}
+
+/**
+ * cell_forcing_chain
+ *
+ * For each unsolved box try every candidate it has. One of them has to be right.
+ */
+pub fn cell_forcing_chain(sudoku: &Sudoku) -> Option<ForcingStep> {
+    let board = BitBoard::from_sudoku(sudoku);
+
+    for idx in 0..81 {
+        if board.values[idx] != 0 {
+            continue;
+        }
+
+        let assumptions: Vec<(usize, u8)> = (1..=9)
+            .filter(|&value| board.is_poss(idx, value))
+            .map(|value| (idx, value))
+            .collect();
+
+        let step = forcing_branches(&board, ForcingTechnique::CellForcingChain, &assumptions);
+        if step.is_some() {
+            return step;
+        }
+    }
+    None
+}
+
+/**
+ * unit_forcing_chain
+ *
+ * For each row, column and cell, and each value not yet placed in it, try the value
+ * in every box of the house that could hold it. One of them has to be right.
+ */
+pub fn unit_forcing_chain(sudoku: &Sudoku) -> Option<ForcingStep> {
+    let board = BitBoard::from_sudoku(sudoku);
+
+    for (house, indexes) in HOUSES.iter().enumerate() {
+        for value in 1..=9u8 {
+            if board.placed[house] & (ON << value) != 0 {
+                continue;
+            }
+
+            let assumptions: Vec<(usize, u8)> = indexes
+                .iter()
+                .filter(|&&idx| board.values[idx] == 0 && board.is_poss(idx, value))
+                .map(|&idx| (idx, value))
+                .collect();
+
+            // Only one place to go is a single position, which is for the simple
+            // solvers to find.
+            if assumptions.len() < 2 {
+                continue;
+            }
+
+            let step = forcing_branches(&board, ForcingTechnique::UnitForcingChain, &assumptions);
+            if step.is_some() {
+                return step;
+            }
+        }
+    }
+    None
+}
+
+/**
+ * digit_nishio
+ *
+ * Look at a single value on its own. For each box it could go in, pretend it goes
+ * there and follow through only that value - removing it from every box that
+ * shares a house, and placing it when a house only has one spot left. If some
+ * house ends up with nowhere for the value, it can't go in the box we started in.
+ */
+pub fn digit_nishio(sudoku: &Sudoku) -> Option<ForcingStep> {
+    let board = BitBoard::from_sudoku(sudoku);
+
+    for value in 1..=9u8 {
+        let mut placed = [false; 81];
+        let mut poss = [false; 81];
+        for idx in 0..81 {
+            placed[idx] = board.values[idx] == value;
+            poss[idx] = board.values[idx] == 0 && board.is_poss(idx, value);
+        }
+
+        for idx in 0..81 {
+            if !poss[idx] {
+                continue;
+            }
+
+            if let Err(contradiction) = nishio_branch(placed, poss, idx) {
+                let (col, row) = col_row(idx);
+                return Some(ForcingStep {
+                    technique: ForcingTechnique::DigitNishio,
+                    branches: vec![Branch {
+                        col,
+                        row,
+                        value,
+                        contradiction: Some(contradiction),
+                    }],
+                    placements: Vec::new(),
+                    eliminations: vec![(col, row, value)],
+                });
+            }
+        }
+    }
+    None
+}
+
+// Follow a single value through from being placed at idx. Only cares about the one
+// value, so the arrays are just "is it here" and "could it be here".
+fn nishio_branch(
+    mut placed: [bool; 81],
+    mut poss: [bool; 81],
+    idx: usize,
+) -> Result<(), Contradiction> {
+    let mut to_place = vec![idx];
+
+    while let Some(idx) = to_place.pop() {
+        if placed[idx] {
+            continue;
+        }
+        placed[idx] = true;
+        poss[idx] = false;
+
+        for &other in PEERS[idx].iter() {
+            poss[other] = false;
+        }
+
+        for (house, indexes) in HOUSES.iter().enumerate() {
+            let count = indexes.iter().filter(|&&other| placed[other]).count();
+            if count > 1 {
+                return Err(Contradiction {
+                    house,
+                    reason: "value repeated",
+                });
+            }
+            if count == 1 {
+                continue;
+            }
+
+            let spots: Vec<usize> = indexes
+                .iter()
+                .copied()
+                .filter(|&other| poss[other])
+                .collect();
+            match spots.len() {
+                0 => {
+                    return Err(Contradiction {
+                        house,
+                        reason: "value has nowhere to go",
+                    })
+                }
+                1 => to_place.push(spots[0]),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * forcing_step
+ *
+ * Try each of the techniques in here from cheapest to dearest and return the
+ * first thing any of them find.
+ */
+pub fn forcing_step(sudoku: &Sudoku) -> Option<ForcingStep> {
+    digit_nishio(sudoku)
+        .or_else(|| cell_forcing_chain(sudoku))
+        .or_else(|| unit_forcing_chain(sudoku))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From top95, propagating gets stuck on this one straight away.
+    const STUCK: &str =
+        "48.3............71.2.......7.5....6....2..8.............1.76...3.....4......5....";
+
+    fn stuck_sudoku() -> Sudoku {
+        let mut sudoku = Sudoku::from_line(STUCK);
+        propagate(&mut sudoku).unwrap();
+        sudoku
+    }
+
+    #[test]
+    fn test_propagate_finds_contradiction() {
+        // Two 1s in the top row can't be solved.
+        let mut sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        sudoku.cells[TOP_LFT].boxes[TOP_LFT].set_val(1);
+
+        let contradiction = propagate(&mut sudoku).unwrap_err();
+        assert_eq!(contradiction.house, 0);
+        assert_eq!(format!("{}", contradiction), "row 1: value repeated");
+    }
+
+    #[test]
+    fn test_propagate_solves() {
+        // First of top95, which single_position and naked_set can do on their own if
+        // they keep going long enough.
+        let mut sudoku = Sudoku::from_line(
+            "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......",
+        );
+        propagate(&mut sudoku).unwrap();
+        assert!(sudoku.solved());
+    }
+
+    #[test]
+    fn test_forcing_steps_are_sound() {
+        // Every step must keep the one true solution possible, so apply steps until we run
+        // out and check it never puts us in a broken state.
+        let mut sudoku = stuck_sudoku();
+        let mut steps = 0;
+        while let Some(step) = forcing_step(&sudoku) {
+            assert!(!step.placements.is_empty() || !step.eliminations.is_empty());
+            step.apply(&mut sudoku);
+            assert_eq!(propagate(&mut sudoku), Ok(()));
+            steps += 1;
+            if sudoku.solved() {
+                break;
+            }
+        }
+        assert!(steps > 0);
+        assert!(sudoku.solved());
+    }
+
+    #[test]
+    fn test_nishio_reports_failed_branch() {
+        let step = digit_nishio(&stuck_sudoku()).unwrap();
+        assert_eq!(step.technique, ForcingTechnique::DigitNishio);
+        assert_eq!(step.branches.len(), 1);
+        assert!(step.branches[0].contradiction.is_some());
+
+        let branch = step.branches[0];
+        assert_eq!(
+            step.eliminations,
+            vec![(branch.col, branch.row, branch.value)]
+        );
+    }
+
+    #[test]
+    fn test_cell_forcing_chain() {
+        let sudoku = stuck_sudoku();
+        let step = cell_forcing_chain(&sudoku).unwrap();
+        assert_eq!(step.technique, ForcingTechnique::CellForcingChain);
+
+        // All the branches tried are for the same box.
+        let first = step.branches[0];
+        assert!(step
+            .branches
+            .iter()
+            .all(|branch| branch.col == first.col && branch.row == first.row));
+        assert!(format!("{}", step).starts_with("Cell Forcing Chain:"));
+    }
+
+    #[test]
+    fn test_unit_forcing_chain() {
+        let sudoku = stuck_sudoku();
+        let step = unit_forcing_chain(&sudoku).unwrap();
+        assert_eq!(step.technique, ForcingTechnique::UnitForcingChain);
+
+        // All the branches tried are for the same value.
+        let first = step.branches[0];
+        assert!(step
+            .branches
+            .iter()
+            .all(|branch| branch.value == first.value));
+    }
+}
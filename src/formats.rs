@@ -0,0 +1,334 @@
+/*
+ * All the file formats in one place, so anything that takes sudokus in or puts them
+ * out can work with any of them.
+ *
+ * The format of a file is worked out from its extension, or if that doesn't say
+ * (like for stdin) from what the text looks like.
+ */
+use crate::reader::RecordReader;
+use crate::sadman::{self, SdkPuzzle};
+use crate::sudoku::Sudoku;
+use std::fs;
+use std::io::Read;
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum FileType {
+    Simple,
+    Multi,
+    Possibles,
+    Sdk,
+    Sdm,
+    Candidates,
+    #[cfg(feature = "json")]
+    Json,
+}
+
+// The names used for formats on the command line, and the type each one means.
+pub const FORMAT_NAMES: &[(&str, FileType)] = &[
+    ("ss", FileType::Simple),
+    ("txt", FileType::Multi),
+    ("possibles", FileType::Possibles),
+    ("sdk", FileType::Sdk),
+    ("sdm", FileType::Sdm),
+    ("cand", FileType::Candidates),
+    #[cfg(feature = "json")]
+    ("json", FileType::Json),
+];
+
+/**
+ * Sudokus
+ *
+ * Everything read from a file: the sudokus and, for .sdk files, the metadata about
+ * the puzzle so it can be written out again.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub struct Sudokus {
+    pub sudokus: Vec<Sudoku>,
+    pub metadata: Vec<(char, String)>,
+}
+
+impl FileType {
+    /**
+     * from_name
+     *
+     * The format for a name on the command line, like "ss" or "txt".
+     */
+    pub fn from_name(name: &str) -> Option<FileType> {
+        FORMAT_NAMES
+            .iter()
+            .find(|(format, _)| *format == name)
+            .map(|(_, file_type)| *file_type)
+    }
+
+    /**
+     * from_extension
+     *
+     * Guess the type of a file from the end of its name. Anything ending .ss is
+     * taken as Simple even though some are really possibles grids, which read
+     * sorts out.
+     */
+    pub fn from_extension(filename: &str) -> Option<FileType> {
+        let (_, extension) = filename.rsplit_once('.')?;
+        FileType::from_name(&extension.to_lowercase())
+    }
+
+    /**
+     * sniff
+     *
+     * Guess the type from the text itself, going with one sudoku a line if it
+     * doesn't look like anything else.
+     */
+    pub fn sniff(text: &str) -> FileType {
+        let start = text.trim_start();
+        if start.starts_with('╔') {
+            return FileType::Possibles;
+        }
+        if start.starts_with('|') {
+            return FileType::Simple;
+        }
+        if text.lines().any(|line| line.trim() == "[Puzzle]") {
+            return FileType::Sdk;
+        }
+        #[cfg(feature = "json")]
+        if start.starts_with('{') || start.starts_with('[') {
+            return FileType::Json;
+        }
+
+        // Candidates are either far too long for a line of values or have a space
+        // between every box.
+        let first = text
+            .lines()
+            .map(|line| line.trim())
+            .find(|line| !line.is_empty() && !line.starts_with('#'));
+        match first {
+            Some(line) if line.len() == 729 || line.split_whitespace().count() == 81 => {
+                FileType::Candidates
+            }
+            _ => FileType::Multi,
+        }
+    }
+
+    /**
+     * read
+     *
+     * Read every sudoku in some text of this type, or say what's wrong with it.
+     */
+    pub fn read(self, text: &str) -> Result<Sudokus, String> {
+        let mut metadata = Vec::new();
+        let sudokus = match self {
+            // Some .ss files are really possibles grids, like test/xwing_ready.ss.
+            FileType::Simple if text.trim_start().starts_with('╔') => {
+                read_grids(text, Sudoku::parse_possibles)?
+            }
+            FileType::Simple => read_grids(text, Sudoku::parse_ss)?,
+            FileType::Possibles => read_grids(text, Sudoku::parse_possibles)?,
+            FileType::Multi | FileType::Sdm => RecordReader::new(text.as_bytes())
+                .map(|record| record.map(|record| record.sudoku))
+                .collect::<Result<Vec<Sudoku>, _>>()
+                .map_err(|error| error.to_string())?,
+            FileType::Sdk => {
+                let puzzle = SdkPuzzle::parse(text)?;
+                metadata = puzzle.metadata;
+                vec![puzzle.sudoku]
+            }
+            FileType::Candidates => {
+                Sudoku::read_candidates(text).map_err(|error| error.to_string())?
+            }
+            #[cfg(feature = "json")]
+            FileType::Json => crate::json::from_json(text)?,
+        };
+        Ok(Sudokus { sudokus, metadata })
+    }
+
+    /**
+     * write
+     *
+     * All the sudokus as text of this type, the grids one after another, each read
+     * back as its own sudoku. A .sdk file holds exactly one puzzle, so it's an error
+     * to write any other number to one.
+     */
+    pub fn write(self, sudokus: &Sudokus) -> Result<String, String> {
+        let each = |write: fn(Sudoku) -> String| -> String {
+            sudokus
+                .sudokus
+                .iter()
+                .map(|sudoku| write(*sudoku))
+                .collect()
+        };
+        Ok(match self {
+            FileType::Simple => each(Sudoku::to_ss_string),
+            FileType::Possibles => each(Sudoku::to_possibles_string),
+            FileType::Multi => each(|sudoku| sudoku.to_line_string() + "\n"),
+            FileType::Candidates => each(|sudoku| sudoku.to_candidates_string() + "\n"),
+            FileType::Sdk => match sudokus.sudokus[..] {
+                [sudoku] => SdkPuzzle {
+                    sudoku,
+                    metadata: sudokus.metadata.clone(),
+                }
+                .to_sdk_string(),
+                _ => {
+                    return Err(format!(
+                        "An sdk file holds one sudoku, not {}",
+                        sudokus.sudokus.len()
+                    ))
+                }
+            },
+            FileType::Sdm => sadman::to_sdm_string(&sudokus.sudokus),
+            #[cfg(feature = "json")]
+            FileType::Json => crate::json::to_json(&sudokus.sudokus) + "\n",
+        })
+    }
+}
+
+// Read every grid in a file of .ss or possibles grids. Each grid starts at a line
+// that isn't blank after a blank line, or at the top line of a possibles grid, so
+// grids written one after another come back apart. Grids are numbered from 1 in
+// the errors when there's more than one.
+fn read_grids(
+    text: &str,
+    parse: fn(&str) -> Result<Sudoku, &'static str>,
+) -> Result<Vec<Sudoku>, String> {
+    let mut grids: Vec<String> = Vec::new();
+    let mut blank = true;
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            blank = true;
+            continue;
+        }
+        if blank || line.starts_with('╔') || grids.is_empty() {
+            grids.push(String::new());
+        }
+        blank = false;
+        grids.last_mut().expect("Just pushed").push_str(line);
+    }
+
+    match grids.len() {
+        0 => Err(parse(text).expect_err("Nothing to read").to_string()),
+        1 => Ok(vec![parse(&grids[0])?]),
+        _ => grids
+            .iter()
+            .enumerate()
+            .map(|(idx, grid)| parse(grid).map_err(|error| format!("grid {}: {}", idx + 1, error)))
+            .collect(),
+    }
+}
+
+/**
+ * read_file
+ *
+ * Read every sudoku from a file, or stdin for "-". The type is the one given, or
+ * goes by the extension, or failing that what the text looks like.
+ */
+pub fn read_file(filename: &str, file_type: Option<FileType>) -> Result<Sudokus, String> {
+    let text = if filename == "-" {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|error| format!("stdin: {}", error))?;
+        text
+    } else {
+        fs::read_to_string(filename).map_err(|error| format!("{}: {}", filename, error))?
+    };
+
+    let file_type = file_type
+        .or_else(|| FileType::from_extension(filename))
+        .unwrap_or_else(|| FileType::sniff(&text));
+    file_type.read(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_names() {
+        assert_eq!(FileType::from_name("ss"), Some(FileType::Simple));
+        assert_eq!(FileType::from_name("txt"), Some(FileType::Multi));
+        assert_eq!(FileType::from_name("doc"), None);
+        assert_eq!(
+            FileType::from_extension("test/top95.SDM"),
+            Some(FileType::Sdm)
+        );
+        assert_eq!(FileType::from_extension("test/heart.mask"), None);
+        assert_eq!(FileType::from_extension("README"), None);
+    }
+
+    // Every file in test/ should come out as the same type from its text alone.
+    #[test]
+    fn test_sniff() {
+        let expected = [
+            ("test/simple.ss", FileType::Simple),
+            ("test/xwing_ready.ss", FileType::Possibles),
+            ("test/possibles.txt", FileType::Possibles),
+            ("test/top95.txt", FileType::Multi),
+            ("test/annotated.txt", FileType::Multi),
+            ("test/inkala.sdk", FileType::Sdk),
+            ("test/xwing_ready.cand", FileType::Candidates),
+        ];
+        for (filename, file_type) in expected {
+            let text = fs::read_to_string(filename).unwrap();
+            assert_eq!(FileType::sniff(&text), file_type, "{}", filename);
+        }
+    }
+
+    #[test]
+    fn test_read_write() {
+        let top95 = read_file("test/top95.txt", None).unwrap();
+        assert_eq!(top95.sudokus.len(), 95);
+
+        for (name, file_type) in FORMAT_NAMES {
+            if *file_type == FileType::Sdk {
+                continue;
+            }
+            let text = file_type.write(&top95).unwrap();
+            let read = file_type.read(&text).unwrap();
+            match file_type {
+                // Reading .ss grids normalises, so compare what's written again.
+                FileType::Simple => {
+                    assert_eq!(read.sudokus.len(), 95);
+                    assert_eq!(file_type.write(&read).unwrap(), text);
+                }
+                _ => assert_eq!(read, top95, "{}", name),
+            }
+        }
+
+        // A .sdk file is one puzzle, no more and no less.
+        assert_eq!(
+            FileType::Sdk.write(&top95),
+            Err("An sdk file holds one sudoku, not 95".to_string())
+        );
+        let none = Sudokus {
+            sudokus: Vec::new(),
+            metadata: Vec::new(),
+        };
+        assert!(FileType::Sdk.write(&none).is_err());
+        assert_eq!(FileType::Multi.write(&none), Ok(String::new()));
+
+        let inkala = read_file("test/inkala.sdk", None).unwrap();
+        assert_eq!(inkala.metadata.len(), 4);
+        assert_eq!(
+            FileType::Sdk.read(&FileType::Sdk.write(&inkala).unwrap()),
+            Ok(inkala)
+        );
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let error = FileType::Multi.read("123\n").unwrap_err();
+        assert_eq!(error, "line 1: expected 81 boxes");
+        assert!(FileType::Simple.read("|...|\n").is_err());
+        assert!(FileType::Possibles.read("╔═══╗\n").is_err());
+        assert!(FileType::Simple.read("").is_err());
+
+        // Which of several grids is wrong.
+        let two = Sudoku::from_ss("test/simple.ss".to_string())
+            .unwrap()
+            .to_ss_string()
+            + "|...|\n";
+        assert_eq!(
+            FileType::Simple.read(&two).unwrap_err(),
+            "grid 2: expected rows like |...|...|...|"
+        );
+        assert!(read_file("test/missing.txt", None).is_err());
+    }
+}
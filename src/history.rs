@@ -0,0 +1,390 @@
+/*
+ * A history of changes to a sudoku that can be undone and redone, for anything that
+ * lets someone (or the solver) change a grid a bit at a time.
+ *
+ * Every action keeps the boxes it changed as they were before and after, so going
+ * back or forward a step just puts those boxes back rather than keeping a copy of
+ * the whole grid for every step.
+ */
+use crate::rating::Technique;
+use crate::sk_box::Box;
+use crate::sudoku::{Change, Sudoku};
+use std::fmt;
+
+/**
+ * Action
+ *
+ * Something done to the sudoku. Boxes are row major indexes from 0 to 80.
+ */
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Action {
+    SetValue { idx: usize, value: u8 },
+    RemovePossible { idx: usize, value: u8 },
+    SetBox { idx: usize, sk_box: Box },
+    AutoMarks,
+    Apply(Technique),
+}
+
+// Actions are shown the same short way as steps, r1c2=3 and r2c4<>8.
+impl fmt::Display for Action {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let name = |idx: usize| format!("r{}c{}", idx / 9 + 1, idx % 9 + 1);
+        match self {
+            Action::SetValue { idx, value } => write!(formatter, "{}={}", name(*idx), value),
+            Action::RemovePossible { idx, value } => {
+                write!(formatter, "{}<>{}", name(*idx), value)
+            }
+            Action::SetBox { idx, sk_box } => match sk_box.value {
+                Some(value) => write!(formatter, "{}={}", name(*idx), value),
+                None => {
+                    let marks: Vec<String> =
+                        sk_box.get_possibles().iter().map(u16::to_string).collect();
+                    write!(formatter, "{} marks {}", name(*idx), marks.join(" "))
+                }
+            },
+            Action::AutoMarks => formatter.write_str("Auto marks"),
+            Action::Apply(technique) => technique.fmt(formatter),
+        }
+    }
+}
+
+// An action and every box it changed, as (idx, before, after).
+#[derive(PartialEq, Debug, Clone)]
+struct Entry {
+    action: Action,
+    boxes: Vec<(usize, Box, Box)>,
+}
+
+/**
+ * History
+ *
+ * The sudoku as it is now and how it got there. Positions in the history count
+ * actions from the start, so 0 is the sudoku it began with and position() is now.
+ * Anything undone can be redone until something new is done instead.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub struct History {
+    sudoku: Sudoku,
+    done: Vec<Entry>,
+    undone: Vec<Entry>,
+    checkpoints: Vec<(String, usize)>,
+}
+
+impl History {
+    pub fn new(sudoku: Sudoku) -> History {
+        History {
+            sudoku,
+            done: Vec::new(),
+            undone: Vec::new(),
+            checkpoints: Vec::new(),
+        }
+    }
+
+    pub fn sudoku(&self) -> &Sudoku {
+        &self.sudoku
+    }
+
+    pub fn position(&self) -> usize {
+        self.done.len()
+    }
+
+    /**
+     * actions
+     *
+     * Everything done to get to now, first first.
+     */
+    #[cfg(test)]
+    pub fn actions(&self) -> Vec<Action> {
+        self.done.iter().map(|entry| entry.action).collect()
+    }
+
+    // Do something to the sudoku and keep it if it changed anything. Doing something
+    // new means what was undone can't be redone, and checkpoints past here are gone.
+    fn record<F: FnOnce(&mut Sudoku)>(&mut self, action: Action, change: F) -> bool {
+        let before = self.sudoku;
+        change(&mut self.sudoku);
+        let boxes: Vec<(usize, Box, Box)> = (0..81)
+            .map(|idx| (idx, *before.box_at(idx), *self.sudoku.box_at(idx)))
+            .filter(|(_, old, new)| old != new)
+            .collect();
+        if boxes.is_empty() {
+            return false;
+        }
+
+        self.undone.clear();
+        let position = self.position();
+        self.checkpoints.retain(|(_, at)| *at <= position);
+        self.done.push(Entry { action, boxes });
+        true
+    }
+
+    /**
+     * set_val / remove_possible_value / set_box / auto_marks / apply
+     *
+     * Do the same as Box::set_val, Box::remove_possible_value, replacing the box
+     * outright (to clear it or put marks back), Sudoku::auto_marks or
+     * Technique::apply and remember it. Returns false, with nothing added to the
+     * history, if it didn't change anything.
+     */
+    pub fn set_val(&mut self, idx: usize, value: u8) -> bool {
+        self.record(Action::SetValue { idx, value }, |sudoku| {
+            sudoku.box_at_mut(idx).set_val(value)
+        })
+    }
+
+    pub fn remove_possible_value(&mut self, idx: usize, value: u8) -> bool {
+        self.record(Action::RemovePossible { idx, value }, |sudoku| {
+            sudoku.box_at_mut(idx).remove_possible_value(value as u16)
+        })
+    }
+
+    pub fn set_box(&mut self, idx: usize, sk_box: Box) -> bool {
+        self.record(Action::SetBox { idx, sk_box }, |sudoku| {
+            *sudoku.box_at_mut(idx) = sk_box
+        })
+    }
+
+    pub fn auto_marks(&mut self) -> bool {
+        self.record(Action::AutoMarks, |sudoku| *sudoku = sudoku.auto_marks())
+    }
+
+    pub fn apply(&mut self, technique: Technique) -> bool {
+        self.record(Action::Apply(technique), |sudoku| {
+            technique.apply(sudoku);
+        })
+    }
+
+    /**
+     * undo / redo
+     *
+     * Step back or forward one action, returning the action or None if there's
+     * nothing to go back or forward to.
+     */
+    pub fn undo(&mut self) -> Option<Action> {
+        let entry = self.done.pop()?;
+        for (idx, before, _) in entry.boxes.iter() {
+            *self.sudoku.box_at_mut(*idx) = *before;
+        }
+        let action = entry.action;
+        self.undone.push(entry);
+        Some(action)
+    }
+
+    pub fn redo(&mut self) -> Option<Action> {
+        let entry = self.undone.pop()?;
+        for (idx, _, after) in entry.boxes.iter() {
+            *self.sudoku.box_at_mut(*idx) = *after;
+        }
+        let action = entry.action;
+        self.done.push(entry);
+        Some(action)
+    }
+
+    /**
+     * go_to
+     *
+     * Undo or redo until at a position.
+     */
+    pub fn go_to(&mut self, position: usize) {
+        assert!(position <= self.done.len() + self.undone.len());
+        while self.position() > position {
+            self.undo();
+        }
+        while self.position() < position {
+            self.redo();
+        }
+    }
+
+    /**
+     * checkpoint / checkpoint_position / restore
+     *
+     * Name where the history is now, replacing any checkpoint of the same name, and
+     * get back to it later. Checkpoints go when a new action replaces what was
+     * undone after them.
+     */
+    pub fn checkpoint(&mut self, name: &str) {
+        let position = self.position();
+        self.checkpoints.retain(|(other, _)| other != name);
+        self.checkpoints.push((name.to_string(), position));
+    }
+
+    pub fn checkpoint_position(&self, name: &str) -> Option<usize> {
+        self.checkpoints
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, position)| *position)
+    }
+
+    pub fn restore(&mut self, name: &str) -> bool {
+        match self.checkpoint_position(name) {
+            Some(position) => {
+                self.go_to(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /**
+     * at
+     *
+     * The sudoku as it was, or will be again after redoing, at a position.
+     */
+    pub fn at(&self, position: usize) -> Sudoku {
+        assert!(position <= self.done.len() + self.undone.len());
+        let mut sudoku = self.sudoku;
+        for entry in self.done[position.min(self.done.len())..].iter().rev() {
+            for (idx, before, _) in entry.boxes.iter() {
+                *sudoku.box_at_mut(*idx) = *before;
+            }
+        }
+        let redo = position.saturating_sub(self.done.len());
+        for entry in self.undone.iter().rev().take(redo) {
+            for (idx, _, after) in entry.boxes.iter() {
+                *sudoku.box_at_mut(*idx) = *after;
+            }
+        }
+        sudoku
+    }
+
+    /**
+     * diff
+     *
+     * Every box that's different between two positions, see Sudoku::diff. To see
+     * it the way print_possibles shows a diff:
+     *
+     *     history.at(to).print_possibles(Some(history.at(from)), None, Colour::Auto);
+     */
+    pub fn diff(&self, from: usize, to: usize) -> Vec<Change> {
+        self.at(from).diff(&self.at(to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> History {
+        History::new(Sudoku::from_ss("test/simple.ss".to_string()).unwrap())
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = history();
+        let start = *history.sudoku();
+
+        assert!(history.set_val(0, 9));
+        assert!(history.remove_possible_value(1, 4));
+        assert!(!history.remove_possible_value(1, 4));
+        assert_eq!(history.position(), 2);
+        assert_eq!(history.sudoku().box_at(0).value, Some(9));
+        assert!(!history.sudoku().box_at(1).is_poss(4));
+
+        assert_eq!(
+            history.undo(),
+            Some(Action::RemovePossible { idx: 1, value: 4 })
+        );
+        assert!(history.sudoku().box_at(1).is_poss(4));
+        assert_eq!(history.undo(), Some(Action::SetValue { idx: 0, value: 9 }));
+        assert_eq!(history.undo(), None);
+        assert_eq!(*history.sudoku(), start);
+
+        assert_eq!(history.redo(), Some(Action::SetValue { idx: 0, value: 9 }));
+        assert_eq!(history.sudoku().box_at(0).value, Some(9));
+
+        // Doing something new drops what's left to redo.
+        assert!(history.set_val(2, 1));
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.actions().len(), 2);
+        assert_eq!(history.actions()[1].to_string(), "r1c3=1");
+    }
+
+    #[test]
+    fn test_set_box_and_auto_marks() {
+        let mut history = history();
+        let start = *history.sudoku();
+
+        assert!(history.auto_marks());
+        assert!(!history.auto_marks());
+        assert_eq!(*history.sudoku(), start.auto_marks());
+        let mut marked = *history.sudoku().box_at(0);
+        marked.poss[1] = true;
+        assert!(history.set_box(0, marked));
+        assert!(history.sudoku().box_at(0).is_poss(1));
+
+        let actions: Vec<String> = history.actions().iter().map(Action::to_string).collect();
+        assert_eq!(actions[0], "Auto marks");
+        assert!(actions[1].starts_with("r1c1 marks 1 "));
+        history.go_to(0);
+        assert_eq!(*history.sudoku(), start);
+    }
+
+    #[test]
+    fn test_techniques() {
+        let mut history = history();
+        let start = *history.sudoku();
+        let mut solved = start;
+        solved.solve(None);
+
+        while !history.sudoku().solved() {
+            assert!(history.apply(Technique::SinglePosition));
+        }
+        assert_eq!(*history.sudoku(), solved);
+        assert!(!history.apply(Technique::SinglePosition));
+
+        let end = history.position();
+        history.go_to(0);
+        assert_eq!(*history.sudoku(), start);
+        assert_eq!(history.at(end), solved);
+        history.go_to(1);
+        assert_eq!(
+            history.undo(),
+            Some(Action::Apply(Technique::SinglePosition))
+        );
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let mut history = history();
+        history.checkpoint("start");
+        history.set_val(0, 9);
+        history.set_val(1, 8);
+        history.checkpoint("guess");
+        history.set_val(2, 7);
+
+        assert_eq!(history.checkpoint_position("guess"), Some(2));
+        assert!(history.restore("guess"));
+        assert_eq!(history.sudoku().box_at(1).value, Some(8));
+        assert_eq!(history.sudoku().box_at(2).value, None);
+        assert!(history.restore("start"));
+        assert_eq!(history.position(), 0);
+        assert!(!history.restore("missing"));
+
+        // Going a different way from the start loses the checkpoint further on.
+        history.set_val(3, 1);
+        assert_eq!(history.checkpoint_position("guess"), None);
+        assert_eq!(history.checkpoint_position("start"), Some(0));
+    }
+
+    #[test]
+    fn test_at_and_diff() {
+        let mut history = history();
+        history.set_val(0, 9);
+        history.remove_possible_value(1, 4);
+        history.set_val(2, 7);
+        history.undo();
+
+        // Positions either side of now, including one that's only there to redo.
+        assert!(history.at(1).box_at(1).is_poss(4));
+        assert_eq!(history.at(2), *history.sudoku());
+        assert_eq!(history.at(3).box_at(2).value, Some(7));
+
+        let diff = history.diff(0, 3);
+        let boxes: Vec<usize> = diff.iter().map(|change| change.idx).collect();
+        assert_eq!(boxes, vec![0, 1, 2]);
+        assert_eq!(diff[0].placed, Some(9));
+        assert_eq!(diff[1].removed, vec![4]);
+        assert!(history.diff(2, 2).is_empty());
+        assert_eq!(history.diff(3, 0)[0].cleared, Some(9));
+    }
+}
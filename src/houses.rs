@@ -0,0 +1,157 @@
+/*
+ * Lookup tables for walking around the sudoku by index rather than by reference.
+ *
+ * Every box gets an index from 0 to 80 counting across each row from the top left,
+ * the same order the boxes are in for a one line sudoku. A house is any group of 9
+ * boxes that has to hold 1-9 once each, and they are numbered with rows 0-8 first,
+ * then columns 9-17, then cells 18-26.
+ *
+ * Everything here is worked out at compile time so the solvers can loop over rows,
+ * columns, cells or the boxes a box can "see" without building vectors of references
+ * every time.
+ */
+
+pub const ROW_HOUSE: usize = 0;
+pub const COL_HOUSE: usize = 9;
+pub const CELL_HOUSE: usize = 18;
+
+/**
+ * HOUSES
+ *
+ * The box indexes of each of the 27 houses. Rows go left to right, columns top to
+ * bottom and cells in the same order as the boxes within a Cell.
+ */
+pub const HOUSES: [[usize; 9]; 27] = build_houses();
+
+/**
+ * BOX_HOUSES
+ *
+ * The row, column and cell house that each box is in.
+ */
+pub const BOX_HOUSES: [[usize; 3]; 81] = build_box_houses();
+
+/**
+ * PEERS
+ *
+ * The 20 other boxes that share a row, column or cell with each box.
+ */
+pub const PEERS: [[usize; 20]; 81] = build_peers();
+
+/**
+ * CELL_BOX
+ *
+ * The (cell, box) pair each index is stored at in a Sudoku.
+ */
+pub const CELL_BOX: [(usize, usize); 81] = build_cell_box();
+
+// Turns the cell and box within it back into an index.
+pub const fn box_index(cell: usize, box_idx: usize) -> usize {
+    ((cell / 3) * 3 + box_idx / 3) * 9 + (cell % 3) * 3 + box_idx % 3
+}
+
+// Const fns can't use for loops yet, so these are all while loops.
+const fn build_houses() -> [[usize; 9]; 27] {
+    let mut result = [[0; 9]; 27];
+    let mut n = 0;
+    while n < 9 {
+        let mut i = 0;
+        while i < 9 {
+            result[ROW_HOUSE + n][i] = n * 9 + i;
+            result[COL_HOUSE + n][i] = i * 9 + n;
+            result[CELL_HOUSE + n][i] = box_index(n, i);
+            i += 1;
+        }
+        n += 1;
+    }
+    result
+}
+
+const fn build_box_houses() -> [[usize; 3]; 81] {
+    let mut result = [[0; 3]; 81];
+    let mut idx = 0;
+    while idx < 81 {
+        let row = idx / 9;
+        let col = idx % 9;
+        result[idx] = [
+            ROW_HOUSE + row,
+            COL_HOUSE + col,
+            CELL_HOUSE + (row / 3) * 3 + col / 3,
+        ];
+        idx += 1;
+    }
+    result
+}
+
+const fn build_peers() -> [[usize; 20]; 81] {
+    let box_houses = build_box_houses();
+    let mut result = [[0; 20]; 81];
+    let mut idx = 0;
+    while idx < 81 {
+        // Any other box sharing one of the three houses is a peer. Walking the
+        // boxes in order means no peer gets added twice.
+        let mut found = 0;
+        let mut other = 0;
+        while other < 81 {
+            let shares = box_houses[idx][0] == box_houses[other][0]
+                || box_houses[idx][1] == box_houses[other][1]
+                || box_houses[idx][2] == box_houses[other][2];
+            if other != idx && shares {
+                result[idx][found] = other;
+                found += 1;
+            }
+            other += 1;
+        }
+        idx += 1;
+    }
+    result
+}
+
+const fn build_cell_box() -> [(usize, usize); 81] {
+    let mut result = [(0, 0); 81];
+    let mut cell = 0;
+    while cell < 9 {
+        let mut box_idx = 0;
+        while box_idx < 9 {
+            result[box_index(cell, box_idx)] = (cell, box_idx);
+            box_idx += 1;
+        }
+        cell += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::Sudoku;
+
+    #[test]
+    fn test_houses() {
+        assert_eq!(HOUSES[0], [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(HOUSES[COL_HOUSE + 1], [1, 10, 19, 28, 37, 46, 55, 64, 73]);
+        assert_eq!(HOUSES[CELL_HOUSE + 4], [30, 31, 32, 39, 40, 41, 48, 49, 50]);
+        assert_eq!(BOX_HOUSES[40], [4, 13, 22]);
+    }
+
+    #[test]
+    fn test_peers() {
+        for idx in 0..81 {
+            let peers = PEERS[idx];
+            assert!(!peers.contains(&idx));
+            for (other, houses) in BOX_HOUSES.iter().enumerate() {
+                let shares = (0..3).any(|i| BOX_HOUSES[idx][i] == houses[i]);
+                assert_eq!(peers.contains(&other), shares && other != idx);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cell_box() {
+        // Same answer as the 1-9 column and row version.
+        for (idx, &(cell, box_idx)) in CELL_BOX.iter().enumerate() {
+            let (col, row) = (idx % 9 + 1, idx / 9 + 1);
+            assert_eq!((cell, box_idx), Sudoku::col_row_to_cell_idx(col, row));
+            assert_eq!(box_index(cell, box_idx), idx);
+        }
+    }
+}
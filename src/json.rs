@@ -0,0 +1,179 @@
+/*
+ * JSON import and export, only built with the json feature so the solver itself
+ * doesn't need serde.
+ *
+ * A sudoku is its 9 cells, left to right and top to bottom, each of which is its 9
+ * boxes in the same order:
+ *
+ * {"cells": [{"boxes": [BOX, ...]}, ...]}
+ *
+ * A box is its value, or null if it's not solved yet, and the values still possible
+ * for it from 1 to 9. A solved box has just its own value as a possible value, and
+ * leaving the list out when reading is fine:
+ *
+ * {"value": 5, "candidates": [5]}
+ * {"value": null, "candidates": [1, 4, 7]}
+ *
+ * As with the candidate formats an unsolved box with only one possible value is
+ * read in as solved.
+ *
+ * A solve trace is the sudoku before and after, and every step of the way with the
+ * technique (named as for generate --needs) and what it did. Boxes are given by
+ * their column and row from 1 to 9, and removed only lists the possible values taken
+ * out of boxes that weren't solved by the step:
+ *
+ * {
+ *   "initial": SUDOKU,
+ *   "steps": [
+ *     {
+ *       "technique": "xwing",
+ *       "placed": [{"col": 1, "row": 2, "value": 3}],
+ *       "removed": [{"col": 4, "row": 2, "value": 8}]
+ *     }
+ *   ],
+ *   "final": SUDOKU,
+ *   "solved": true
+ * }
+ */
+use crate::rating::Trace;
+use crate::sk_box::Box;
+use crate::sudoku::Sudoku;
+use serde::{Deserialize, Serialize};
+
+/**
+ * BoxJson
+ *
+ * How a box looks in JSON, which serde goes through to read and write a Box.
+ */
+#[derive(Serialize, Deserialize)]
+pub struct BoxJson {
+    value: Option<u8>,
+    #[serde(default)]
+    candidates: Vec<u8>,
+}
+
+impl From<Box> for BoxJson {
+    fn from(sk_box: Box) -> BoxJson {
+        BoxJson {
+            value: sk_box.value,
+            candidates: (1..=9).filter(|&value| sk_box.is_poss(value)).collect(),
+        }
+    }
+}
+
+impl TryFrom<BoxJson> for Box {
+    type Error = String;
+
+    fn try_from(json: BoxJson) -> Result<Box, String> {
+        let mut bits: u16 = 0;
+        for value in json.candidates.iter().copied().chain(json.value) {
+            if !(1..=9).contains(&value) {
+                return Err(format!("{} isn't a value between 1 and 9", value));
+            }
+            bits |= 1 << value;
+        }
+
+        match json.value {
+            Some(value) if bits != 1 << value => {
+                Err(format!("solved box {} has other possible values", value))
+            }
+            Some(value) => Ok(Box::from_val(value)),
+            None if bits == 0 => Err("box with no possible values".to_string()),
+            None => Ok(Box::from_possibles_bits(bits)),
+        }
+    }
+}
+
+// Either one sudoku or a list of them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Sudokus {
+    One(std::boxed::Box<Sudoku>),
+    Many(Vec<Sudoku>),
+}
+
+/**
+ * to_json / from_json
+ *
+ * Write a list of sudokus, and read either a list or just the one back in.
+ */
+pub fn to_json(sudokus: &[Sudoku]) -> String {
+    serde_json::to_string_pretty(sudokus).expect("Sudokus always make JSON")
+}
+
+pub fn from_json(text: &str) -> Result<Vec<Sudoku>, String> {
+    let sudokus = match serde_json::from_str(text).map_err(|error| error.to_string())? {
+        Sudokus::One(sudoku) => vec![*sudoku],
+        Sudokus::Many(sudokus) => sudokus,
+    };
+    if sudokus.iter().any(|sudoku| sudoku.repeated_value()) {
+        return Err("value repeated".to_string());
+    }
+    Ok(sudokus)
+}
+
+/**
+ * trace_to_json
+ *
+ * A whole solve trace on a single line, so a file of them has one per line.
+ */
+pub fn trace_to_json(trace: &Trace) -> String {
+    serde_json::to_string(trace).expect("Traces always make JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rating;
+
+    #[test]
+    fn test_json_round_trip() {
+        let sudokus = vec![
+            Sudoku::from_possibles("test/xwing_ready.ss".to_string()),
+            Sudoku::from_ss("test/simple.ss".to_string()).unwrap(),
+        ];
+        let json = to_json(&sudokus);
+        assert_eq!(from_json(&json).unwrap(), sudokus);
+
+        // Just the one is fine too.
+        let json = serde_json::to_string(&sudokus[0]).unwrap();
+        assert_eq!(from_json(&json).unwrap(), vec![sudokus[0]]);
+    }
+
+    #[test]
+    fn test_box_json() {
+        let sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        let json = serde_json::to_value(sudoku).unwrap();
+        assert_eq!(
+            json["cells"][0]["boxes"][3],
+            serde_json::json!({"value": 6, "candidates": [6]})
+        );
+        assert_eq!(
+            json["cells"][0]["boxes"][0],
+            serde_json::json!({"value": null, "candidates": [2, 3, 4, 5, 7]})
+        );
+
+        let read = |text: &str| serde_json::from_str::<Box>(text).map_err(|e| e.to_string());
+        assert_eq!(read(r#"{"value": 4}"#).unwrap(), Box::from_val(4));
+        assert_eq!(
+            read(r#"{"value": null, "candidates": [7]}"#).unwrap(),
+            Box::from_val(7)
+        );
+        assert!(read(r#"{"value": null, "candidates": []}"#).is_err());
+        assert!(read(r#"{"value": null, "candidates": [0, 1]}"#).is_err());
+        assert!(read(r#"{"value": 4, "candidates": [4, 5]}"#).is_err());
+    }
+
+    #[test]
+    fn test_trace_json() {
+        let sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        let trace = rating::trace(&sudoku);
+        let json = trace_to_json(&trace);
+        assert!(!json.contains('\n'));
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["solved"], true);
+        assert_eq!(value["steps"][0]["technique"], "single_position");
+        assert_eq!(serde_json::from_str::<Trace>(&json).unwrap(), trace);
+    }
+}
@@ -0,0 +1,27 @@
+/*
+ * Everything that isn't the command line, so the benchmarks in benches/ can get at
+ * it too.
+ */
+pub mod batch;
+pub mod bitboard;
+pub mod booklet;
+pub mod candidates;
+pub mod constants;
+pub mod forcing;
+pub mod formats;
+pub mod history;
+pub mod houses;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod play;
+pub mod rating;
+pub mod reader;
+pub mod replay;
+pub mod sadman;
+pub mod sk_box;
+pub mod sk_cell;
+pub mod solvers;
+pub mod sudoku;
+pub mod sudoku_builder;
+pub mod svg;
+pub mod transform;
@@ -3,66 +3,856 @@
 // TODO  Clean up all the unused fucntions that are used in tests but not in main code to be
 //       used from main code
 
-mod constants;
-mod sk_box;
-mod sk_cell;
-mod solvers;
-mod sudoku;
-mod sudoku_builder;
-use crate::sudoku::*;
-use std::env;
-
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let mut filename: &String = &"".to_string();
-    let mut file_type: FileType = FileType::Simple;
-    let mut sudokus: Vec<sudoku::Sudoku> = Vec::new();
-    //Simple,
-    //Pretty,
-    //Multi,
-    //Possibles,
-
-    if args.len() == 2 {
-        // One arg means just a filename to to solve, type already
-        // defaulted to 'Simple'
-        filename = &args[1];
-    } else if args.len() == 3 {
-        // First argument to be a switch in the form "-X" where X
-        // denotes the type of files to read.
-        assert!(args[1].len() == 2);
-        assert!(args[1].starts_with('-'));
-        file_type = match args[1].chars().nth(1).unwrap() {
-            's' => FileType::Simple,
-            'm' => FileType::Multi,
-            'p' => FileType::Possibles,
-            _ => FileType::Simple,
-        };
-
-        filename = &args[2];
-    }
-
-    // Parse the input file provided into a sudoku depending on the type
-    if matches!(file_type, FileType::Simple) {
-        sudokus.push(sudoku::Sudoku::from_ss(filename.to_string()).unwrap());
-    } else if matches!(file_type, FileType::Possibles) {
-        sudokus.push(Sudoku::from_possibles(filename.to_string()));
-    } else if matches!(file_type, FileType::Multi) {
-        sudokus = Sudoku::from_txt(filename.to_string());
-    }
-
-    // Now just solve all the sudokus in the vector. Will only be one for most cases.
-    //
-    for mut sudoku in sudokus {
-        println!("Solving:");
-        sudoku.print_ss();
-
-        sudoku.solve(true);
+use clap::{Args, Parser, Subcommand};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rust_sudoku_solver::bitboard::BitBoard;
+use rust_sudoku_solver::formats::{FileType, Sudokus};
+#[cfg(feature = "json")]
+use rust_sudoku_solver::json;
+use rust_sudoku_solver::rating::Technique;
+use rust_sudoku_solver::sudoku::*;
+use rust_sudoku_solver::sudoku_builder::{Budget, Mask, Symmetry, Target};
+use rust_sudoku_solver::{
+    batch, booklet, formats, play, rating, reader, replay, sadman, sudoku, sudoku_builder, svg,
+};
+use std::collections::HashSet;
+use std::process::ExitCode;
+use std::time::Duration;
 
-        if sudoku.solved() {
+// Exit codes, so scripts can tell what went wrong. Clap uses 2 for bad arguments.
+const FAILED: u8 = 1;
+const BAD_INPUT: u8 = 3;
+const NO_SOLUTION: u8 = 4;
+const MANY_SOLUTIONS: u8 = 5;
+
+const EXIT_CODES: &str = "Exit codes:
+  0  Everything went fine
+  1  Something else went wrong, like generate running out of time
+  2  Bad arguments
+  3  The input couldn't be read
+  4  A sudoku has no solution
+  5  A sudoku has more than one solution";
+
+/// Solve, rate, make and convert sudokus.
+#[derive(Parser)]
+#[command(name = "rust_sudoku_solver", after_help = EXIT_CODES)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Solve every sudoku in a file
+    Solve {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+        /// Show the sudoku after every step of the way
+        #[arg(short, long)]
+        verbose: bool,
+        /// When to colour in the boxes each step changes: always, never, or auto for
+        /// only when stderr is a terminal
+        #[arg(long, default_value = "auto", value_parser = colour_name)]
+        colour: Colour,
+    },
+    /// Say how hard each sudoku is by the hardest technique needed to solve it
+    Rate {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Make a new minimal puzzle with exactly one solution
+    Generate {
+        /// Seed to make the same puzzle again, picked at random if not given
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Symmetry of the givens: none, 180, 90, horizontal, vertical, diagonal or
+        /// antidiagonal
+        #[arg(long, default_value = "none", value_parser = symmetry_name)]
+        symmetry: Symmetry,
+        /// The hardest technique needed must be at least this hard
+        #[arg(long, value_parser = technique_name)]
+        easiest: Option<Technique>,
+        /// The hardest technique needed can be no harder than this
+        #[arg(long, value_parser = technique_name)]
+        hardest: Option<Technique>,
+        /// This technique has to come up in the solve
+        #[arg(long, value_parser = technique_name)]
+        needs: Option<Technique>,
+        /// Give up after making this many puzzles
+        #[arg(long, default_value_t = 1000)]
+        attempts: usize,
+        /// Give up after this many seconds
+        #[arg(long, default_value_t = 60)]
+        seconds: u64,
+        /// Only put givens where the mask file has an 'x'
+        #[arg(long)]
+        mask: Option<String>,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Check every sudoku has exactly one solution
+    Validate {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Write sudokus out in another format
+    Convert {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Show the next step towards solving each sudoku
+    Hint {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Write each sudoku the same way as any other that's just it moved around or
+    /// relabelled
+    Canonicalize {
+        #[command(flatten)]
+        input: Input,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Play a sudoku full screen in the terminal
+    Play {
+        #[command(flatten)]
+        input: Input,
+        /// Which sudoku in the file to play, counting from 1
+        #[arg(long, default_value_t = 1)]
+        number: usize,
+    },
+    /// Step through how a sudoku gets solved, a technique at a time
+    Replay {
+        #[command(flatten)]
+        input: Input,
+        /// Which sudoku in the file to replay, counting from 1
+        #[arg(long, default_value_t = 1)]
+        number: usize,
+        /// Milliseconds between steps when playing
+        #[arg(long, default_value_t = 1000)]
+        delay: u64,
+        /// Start playing straight away instead of paused on the first step
+        #[arg(long)]
+        autoplay: bool,
+    },
+    /// Solve a file of one line sudokus on every core, a line out for each line in
+    Batch {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Print random variants of each one line sudoku, the same puzzle moved around
+    Spin {
+        #[command(flatten)]
+        input: Input,
+        /// Number of variants of each
+        #[arg(short = 'n', long, default_value_t = 1)]
+        count: usize,
+        /// Seed to make the same variants again, picked at random if not given
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Print each one line sudoku unless it's the same puzzle as one before it
+    Dedupe {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Draw the first sudoku as SVG for printing
+    Svg {
+        #[command(flatten)]
+        input: Input,
+        /// File to write to instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Draw it solved, with the givens picked out
+        #[arg(long)]
+        solve: bool,
+        /// Draw the possible values of unsolved boxes
+        #[arg(long)]
+        marks: bool,
+        /// Boxes to shade, like r1c1,r2c2
+        #[arg(long, value_delimiter = ',', value_parser = box_name)]
+        highlight: Vec<usize>,
+    },
+    /// Lay out every sudoku for printing, with the answers at the back
+    Booklet {
+        #[command(flatten)]
+        input: Input,
+        /// HTML file to write, or an SVG file name to number for each page
+        #[arg(short, long)]
+        output: String,
+        /// Puzzles on each page
+        #[arg(long, default_value_t = 2, value_parser = per_page_range())]
+        per_page: usize,
+        /// Title at the top of each page
+        #[arg(long, default_value = "Sudoku")]
+        title: String,
+    },
+    /// Print every solve as a line of JSON with each step it took
+    #[cfg(feature = "json")]
+    Trace {
+        #[command(flatten)]
+        input: Input,
+    },
+}
+
+#[derive(Args)]
+struct Input {
+    /// File to read, or - for stdin
+    #[arg(default_value = "-")]
+    file: String,
+    /// Format of the input, if not going by its extension or what it looks like:
+    /// ss, txt, possibles, sdk, sdm, cand or json (with the json feature)
+    #[arg(long, value_parser = format_name)]
+    from: Option<FileType>,
+}
+
+#[derive(Args)]
+struct Output {
+    /// File to write to instead of stdout
+    #[arg(short, long)]
+    output: Option<String>,
+    /// Format to write, if not going by the output file's extension (txt otherwise)
+    #[arg(long, value_parser = format_name)]
+    to: Option<FileType>,
+}
+
+fn format_name(name: &str) -> Result<FileType, String> {
+    FileType::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = formats::FORMAT_NAMES.iter().map(|(n, _)| *n).collect();
+        format!("expected one of {}", names.join(", "))
+    })
+}
+
+fn technique_name(name: &str) -> Result<Technique, String> {
+    Technique::from_name(name).ok_or_else(|| {
+        "expected single_position, naked_set, candidate_line, xwing, digit_nishio, \
+         cell_forcing_chain or unit_forcing_chain"
+            .to_string()
+    })
+}
+
+fn colour_name(name: &str) -> Result<Colour, String> {
+    Colour::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = sudoku::COLOURS.iter().map(|c| c.name()).collect();
+        format!("expected one of {}", names.join(", "))
+    })
+}
+
+fn symmetry_name(name: &str) -> Result<Symmetry, String> {
+    Symmetry::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = sudoku_builder::SYMMETRIES
+            .iter()
+            .map(|s| s.name())
+            .collect();
+        format!("expected one of {}", names.join(", "))
+    })
+}
+
+fn box_name(name: &str) -> Result<usize, String> {
+    svg::parse_box_name(name).ok_or_else(|| "boxes are named like r1c1".to_string())
+}
+
+fn per_page_range() -> clap::builder::RangedU64ValueParser<usize> {
+    clap::builder::RangedU64ValueParser::new().range(1..=booklet::MAX_PER_PAGE as u64)
+}
+
+/**
+ * Failure
+ *
+ * Why a command stopped, with the exit code for it and what to tell the user.
+ */
+struct Failure {
+    code: u8,
+    message: String,
+}
+
+impl Failure {
+    fn new(code: u8, message: String) -> Failure {
+        Failure { code, message }
+    }
+}
+
+type Outcome = Result<(), Failure>;
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Solve {
+            input,
+            output,
+            verbose,
+            colour,
+        } => solve(&input, &output, verbose.then_some(colour)),
+        Command::Rate { input } => rate(&input),
+        Command::Generate {
+            seed,
+            symmetry,
+            easiest,
+            hardest,
+            needs,
+            attempts,
+            seconds,
+            mask,
+            output,
+        } => {
+            let target = Target {
+                band: easiest.unwrap_or(Technique::SinglePosition)
+                    ..=hardest.unwrap_or(Technique::UnitForcingChain),
+                needs,
+            };
+            let budget = Budget {
+                attempts,
+                time: Duration::from_secs(seconds),
+            };
+            let rated = easiest.is_some() || hardest.is_some() || needs.is_some();
+            generate(
+                seed,
+                symmetry,
+                rated.then_some(target),
+                &budget,
+                mask,
+                &output,
+            )
+        }
+        Command::Validate { input } => validate(&input),
+        Command::Convert { input, output } => {
+            read(&input).and_then(|sudokus| output.write(&sudokus))
+        }
+        Command::Hint { input } => hint(&input),
+        Command::Canonicalize { input, output } => canonicalize(&input, &output),
+        Command::Play { input, number } => play(&input, number),
+        Command::Replay {
+            input,
+            number,
+            delay,
+            autoplay,
+        } => replay(&input, number, delay, autoplay),
+        Command::Batch { input } => batch(&input),
+        Command::Spin { input, count, seed } => spin(&input, count, seed),
+        Command::Dedupe { input } => dedupe(&input),
+        Command::Svg {
+            input,
+            output,
+            solve,
+            marks,
+            highlight,
+        } => draw_svg(&input, output, solve, marks, highlight),
+        Command::Booklet {
+            input,
+            output,
+            per_page,
+            title,
+        } => make_booklet(&input, &output, per_page, &title),
+        #[cfg(feature = "json")]
+        Command::Trace { input } => trace(&input),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("{}", failure.message);
+            ExitCode::from(failure.code)
+        }
+    }
+}
+
+// Read every sudoku from the input.
+fn read(input: &Input) -> Result<Sudokus, Failure> {
+    formats::read_file(&input.file, input.from).map_err(|error| Failure::new(BAD_INPUT, error))
+}
+
+impl Output {
+    // Write the sudokus where asked, in the format asked for.
+    fn write(&self, sudokus: &Sudokus) -> Outcome {
+        let file_type = self
+            .to
+            .or_else(|| self.output.as_deref().and_then(FileType::from_extension))
+            .unwrap_or(FileType::Multi);
+        let text = file_type
+            .write(sudokus)
+            .map_err(|error| Failure::new(BAD_INPUT, error))?;
+        match &self.output {
+            Some(filename) => write_file(filename, &text),
+            None => {
+                print!("{}", text);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_file(filename: &str, text: &str) -> Outcome {
+    std::fs::write(filename, text)
+        .map_err(|error| Failure::new(FAILED, format!("{}: {}", filename, error)))
+}
+
+// Check a sudoku has exactly one solution, saying what's wrong if not. Sudokus are
+// numbered from 1 in the messages.
+fn check_solutions(number: usize, sudoku: &Sudoku) -> Outcome {
+    match BitBoard::from_sudoku(sudoku).count_solutions(2) {
+        1 => Ok(()),
+        0 => Err(Failure::new(
+            NO_SOLUTION,
+            format!("Sudoku {} has no solution", number),
+        )),
+        _ => Err(Failure::new(
+            MANY_SOLUTIONS,
+            format!("Sudoku {} has more than one solution", number),
+        )),
+    }
+}
+
+// Carry on through every sudoku, but remember the worst thing that went wrong so the
+// exit code says so at the end.
+fn worst(outcome: Outcome, failure: &mut Option<Failure>) {
+    if let Err(new) = outcome {
+        eprintln!("{}", new.message);
+        keep_worst(new, failure);
+    }
+}
+
+fn keep_worst(new: Failure, failure: &mut Option<Failure>) {
+    if failure.as_ref().is_none_or(|old| new.code > old.code) {
+        *failure = Some(new);
+    }
+}
+
+fn finish(failure: Option<Failure>) -> Outcome {
+    match failure {
+        // Already said what went wrong as it happened.
+        Some(failure) => Err(Failure::new(
+            failure.code,
+            "Finished with errors".to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
+// solve
+//
+// Solve every sudoku, writing out the results. Ones with no solution are written out
+// as they were, and with more than one as far as the solver gets. verbose shows the
+// working on stderr, coloured in as it says, so stdout is still just the answers.
+fn solve(input: &Input, output: &Output, verbose: Option<Colour>) -> Outcome {
+    let mut sudokus = read(input)?;
+    let mut failure = None;
+
+    if verbose.is_some() {
+        for (code, value) in sudokus.metadata.iter() {
+            eprintln!("{}: {}", sadman::code_name(*code), value);
+        }
+    }
+
+    for (idx, sudoku) in sudokus.sudokus.iter_mut().enumerate() {
+        let checked = check_solutions(idx + 1, sudoku);
+        let solvable = checked
+            .as_ref()
+            .map_or_else(|f| f.code != NO_SOLUTION, |_| true);
+        worst(checked, &mut failure);
+        if !solvable {
+            continue;
+        }
+
+        if verbose.is_some() {
+            eprintln!("Solving:");
             sudoku.print_ss();
-        } else {
-            sudoku.print_possibles(None, Some("Incomplete Solve".to_string()));
         }
+        sudoku.solve(verbose);
+        if let Some(colour) = verbose {
+            if sudoku.solved() {
+                sudoku.print_ss();
+            } else {
+                sudoku.print_possibles(None, Some("Incomplete Solve".to_string()), colour);
+            }
+        }
+
+        if !sudoku.solved() {
+            worst(
+                Err(Failure::new(
+                    FAILED,
+                    format!("Sudoku {} couldn't be solved all the way", idx + 1),
+                )),
+                &mut failure,
+            );
+        }
+    }
+
+    output.write(&sudokus)?;
+    finish(failure)
+}
+
+// rate
+//
+// Print each sudoku as a line with the hardest technique it needs after it.
+fn rate(input: &Input) -> Outcome {
+    let mut failure = None;
+    for (idx, sudoku) in read(input)?.sudokus.iter().enumerate() {
+        let checked = check_solutions(idx + 1, sudoku);
+        if checked.is_err() {
+            worst(checked, &mut failure);
+            continue;
+        }
+
+        match rating::rate(sudoku) {
+            Some(rating) => println!("{} {}", sudoku.to_line_string(), rating.hardest),
+            None => worst(
+                Err(Failure::new(
+                    FAILED,
+                    format!("Sudoku {} couldn't be solved all the way", idx + 1),
+                )),
+                &mut failure,
+            ),
+        }
+    }
+    finish(failure)
+}
+
+// validate
+//
+// Say whether each sudoku has one solution, none, or more than one.
+fn validate(input: &Input) -> Outcome {
+    let mut failure = None;
+    for (idx, sudoku) in read(input)?.sudokus.iter().enumerate() {
+        let checked = check_solutions(idx + 1, sudoku);
+        match &checked {
+            Ok(()) => println!("{}: one solution", idx + 1),
+            Err(f) if f.code == NO_SOLUTION => println!("{}: no solution", idx + 1),
+            Err(_) => println!("{}: more than one solution", idx + 1),
+        }
+        // Already printed, so just keep the exit code.
+        if let Err(new) = checked {
+            keep_worst(new, &mut failure);
+        }
+    }
+    finish(failure)
+}
+
+// hint
+//
+// Print the next step for each sudoku, like "X Wing: r2c4<>8, r2c7<>8".
+fn hint(input: &Input) -> Outcome {
+    let mut failure = None;
+    for (idx, sudoku) in read(input)?.sudokus.iter().enumerate() {
+        if sudoku.solved() {
+            println!("{}: already solved", idx + 1);
+            continue;
+        }
+        let checked = check_solutions(idx + 1, sudoku);
+        if checked.as_ref().is_err_and(|f| f.code == NO_SOLUTION) {
+            worst(checked, &mut failure);
+            continue;
+        }
+
+        match rating::next_step(sudoku) {
+            Some(step) => println!("{}: {}", idx + 1, step),
+            None => worst(
+                Err(Failure::new(
+                    FAILED,
+                    format!("Sudoku {}: no technique gets anywhere", idx + 1),
+                )),
+                &mut failure,
+            ),
+        }
+    }
+    finish(failure)
+}
+
+// canonicalize
+//
+// Write each sudoku in its canonical form, so any two that are the same puzzle come
+// out the same.
+fn canonicalize(input: &Input, output: &Output) -> Outcome {
+    let mut sudokus = read(input)?;
+    for sudoku in sudokus.sudokus.iter_mut() {
+        *sudoku = sudoku.canonical();
+    }
+    output.write(&sudokus)
+}
+
+// generate
+//
+// Make a new minimal puzzle. Without a seed a random one is picked and shown so the
+// puzzle can be made again. With a target it keeps making puzzles until one needs the
+// right techniques, and a mask makes a puzzle with givens in just the boxes marked with
+// an 'x'.
+fn generate(
+    seed: Option<u64>,
+    symmetry: Symmetry,
+    target: Option<Target>,
+    budget: &Budget,
+    mask: Option<String>,
+    output: &Output,
+) -> Outcome {
+    let seed = match seed {
+        Some(seed) => seed,
+        None => {
+            let seed = rand::random::<u64>();
+            eprintln!("Seed: {}", seed);
+            seed
+        }
+    };
+    let out_of_budget = || Failure::new(FAILED, "No puzzle found within the budget".to_string());
+
+    let puzzle = if let Some(mask) = mask {
+        let mask: Mask = sudoku_builder::read_mask(mask.clone())
+            .map_err(|error| Failure::new(BAD_INPUT, format!("{}: {}", mask, error)))?;
+        sudoku_builder::generate_from_mask(seed, &mask, budget).ok_or_else(out_of_budget)?
+    } else if let Some(target) = target {
+        let (puzzle, rating) = sudoku_builder::generate_rated(seed, symmetry, &target, budget)
+            .ok_or_else(out_of_budget)?;
+        eprintln!("Hardest technique: {}", rating.hardest);
+        puzzle
+    } else {
+        sudoku_builder::generate_minimal(seed, symmetry)
+    };
+
+    output.write(&Sudokus {
+        sudokus: vec![puzzle],
+        metadata: Vec::new(),
+    })
+}
+
+// The sudoku to play or replay, which has to have the one solution.
+fn pick(input: &Input, number: usize) -> Result<Sudoku, Failure> {
+    let sudokus = read(input)?.sudokus;
+    let puzzle = match number.checked_sub(1).and_then(|idx| sudokus.get(idx)) {
+        Some(puzzle) => *puzzle,
+        None => {
+            return Err(Failure::new(
+                BAD_INPUT,
+                format!("No sudoku {}, there are {}", number, sudokus.len()),
+            ))
+        }
+    };
+    check_solutions(number, &puzzle)?;
+    Ok(puzzle)
+}
+
+// play
+//
+// Play one of the sudokus in a file. It has to have just the one solution, so the
+// game knows when it's done and can check the values before giving hints.
+fn play(input: &Input, number: usize) -> Outcome {
+    let puzzle = pick(input, number)?;
+    let solution = BitBoard::from_sudoku(&puzzle).solutions(1)[0].to_sudoku();
+    play::run(play::Game::new(puzzle, solution))
+        .map_err(|error| Failure::new(FAILED, error.to_string()))
+}
+
+// replay
+//
+// Step through the solve of one of the sudokus in a file, showing the pattern each
+// technique found. It has to have just the one solution, the same as for play.
+fn replay(input: &Input, number: usize, delay: u64, autoplay: bool) -> Outcome {
+    let puzzle = pick(input, number)?;
+    let mut replay = replay::Replay::new(&puzzle, Duration::from_millis(delay));
+    replay.playing = autoplay && !replay.steps.is_empty();
+    replay::run(replay).map_err(|error| Failure::new(FAILED, error.to_string()))
+}
+
+// A file to read, or stdin for "-".
+fn open_input(filename: &str) -> Result<Box<dyn std::io::BufRead>, Failure> {
+    if filename == "-" {
+        Ok(Box::new(std::io::stdin().lock()))
+    } else {
+        let file = std::fs::File::open(filename)
+            .map_err(|error| Failure::new(BAD_INPUT, format!("{}: {}", filename, error)))?;
+        Ok(Box::new(std::io::BufReader::new(file)))
+    }
+}
+
+// The input as one sudoku a line, to stream through a line at a time. Files of one
+// sudoku a line, and stdin unless --from says otherwise, are streamed as they are.
+// Anything else is read in whole and turned into lines, numbered by sudoku.
+fn open_lines(input: &Input) -> Result<Box<dyn std::io::BufRead>, Failure> {
+    match input.from.or_else(|| FileType::from_extension(&input.file)) {
+        None | Some(FileType::Multi) | Some(FileType::Sdm) => open_input(&input.file),
+        Some(_) => {
+            let lines = FileType::Multi
+                .write(&read(input)?)
+                .map_err(|error| Failure::new(BAD_INPUT, error))?;
+            Ok(Box::new(std::io::Cursor::new(lines.into_bytes())))
+        }
+    }
+}
+
+// batch
+//
+// Stream the file so huge files don't need to fit in memory. Bad lines still get a
+// line out so the output lines up with the input, but the exit code says so.
+fn batch(input: &Input) -> Outcome {
+    let stdout = std::io::stdout();
+    let mut output = std::io::BufWriter::new(stdout.lock());
+    let problems = batch::solve_stream(open_lines(input)?, &mut output)
+        .map_err(|error| Failure::new(FAILED, error.to_string()))?;
+
+    // Say what went wrong with each line, in the order of the lines.
+    let mut failures: Vec<(usize, Failure)> = problems
+        .unreadable
+        .iter()
+        .map(|error| (error.line, Failure::new(BAD_INPUT, error.to_string())))
+        .collect();
+    for line in problems.unsolvable {
+        let message = format!("line {}: no solution", line);
+        failures.push((line, Failure::new(NO_SOLUTION, message)));
+    }
+    for line in problems.many_solutions {
+        let message = format!("line {}: more than one solution", line);
+        failures.push((line, Failure::new(MANY_SOLUTIONS, message)));
+    }
+    failures.sort_by_key(|(line, _)| *line);
+
+    let mut failure = None;
+    for (_, new) in failures {
+        worst(Err(new), &mut failure);
+    }
+    finish(failure)
+}
+
+// A line that couldn't be read, left out of what's written.
+fn skipping(error: reader::ParseError) -> Outcome {
+    Err(Failure::new(BAD_INPUT, format!("Skipping {}", error)))
+}
+
+// spin
+//
+// Print variants of each sudoku, each one just as hard as it's the same puzzle moved
+// around.
+fn spin(input: &Input, count: usize, seed: Option<u64>) -> Outcome {
+    let seed = match seed {
+        Some(seed) => seed,
+        None => {
+            let seed = rand::random::<u64>();
+            eprintln!("Seed: {}", seed);
+            seed
+        }
+    };
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut failure = None;
+
+    for sudoku in reader::SudokuReader::new(open_lines(input)?) {
+        match sudoku {
+            Ok(sudoku) => {
+                for _ in 0..count {
+                    println!("{}", sudoku.transform(&mut rng).to_line_string());
+                }
+            }
+            Err(error) => worst(skipping(error), &mut failure),
+        }
+    }
+    finish(failure)
+}
+
+// dedupe
+//
+// Print each sudoku unless it's the same puzzle as one already printed, just moved
+// around or relabelled.
+fn dedupe(input: &Input) -> Outcome {
+    let mut failure = None;
+    let mut seen = HashSet::new();
+    let mut duplicates = 0;
+    for record in reader::RecordReader::new(open_lines(input)?) {
+        match record {
+            Ok(record) => {
+                if seen.insert(record.sudoku.canonical().to_line_string()) {
+                    // Keep anything that came after the sudoku on the line.
+                    match record.metadata {
+                        Some(metadata) => {
+                            println!("{} {}", record.sudoku.to_line_string(), metadata)
+                        }
+                        None => println!("{}", record.sudoku.to_line_string()),
+                    }
+                } else {
+                    duplicates += 1;
+                }
+            }
+            Err(error) => worst(skipping(error), &mut failure),
+        }
+    }
+    eprintln!("Removed {} duplicates", duplicates);
+    finish(failure)
+}
+
+// svg
+//
+// Draw the first sudoku for printing, solved as far as it'll go with the givens picked
+// out if asked.
+fn draw_svg(
+    input: &Input,
+    output: Option<String>,
+    solve: bool,
+    marks: bool,
+    highlight: Vec<usize>,
+) -> Outcome {
+    let puzzle = match read(input)?.sudokus.first() {
+        Some(puzzle) => *puzzle,
+        None => return Err(Failure::new(BAD_INPUT, "No sudoku to draw".to_string())),
+    };
+    let mut sudoku = puzzle;
+    let mut options = svg::SvgOptions {
+        pencil_marks: marks,
+        highlight,
+        ..svg::SvgOptions::default()
+    };
+    if solve {
+        check_solutions(1, &puzzle)?;
+        sudoku.solve(None);
+        options.givens = Some(puzzle);
+    }
+
+    let text = svg::render(&sudoku, &options);
+    match output {
+        Some(filename) => write_file(&filename, &text),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+// booklet
+//
+// Lay out every sudoku for printing, rated and with the answers at the back. An SVG
+// file name gets a number added for each page so booklet.svg becomes booklet-1.svg,
+// booklet-2.svg and so on.
+fn make_booklet(input: &Input, output: &str, per_page: usize, title: &str) -> Outcome {
+    let sudokus = read(input)?.sudokus;
+    let mut failure = None;
+    for (idx, sudoku) in sudokus.iter().enumerate() {
+        worst(check_solutions(idx + 1, sudoku), &mut failure);
+    }
+
+    let booklet = booklet::Booklet::new(title, per_page, &sudokus);
+    match output.strip_suffix(".svg") {
+        Some(name) => {
+            for (number, page) in booklet.to_svg_pages().iter().enumerate() {
+                write_file(&format!("{}-{}.svg", name, number + 1), page)?;
+            }
+        }
+        None => write_file(output, &booklet.to_html())?,
+    }
+    finish(failure)
+}
+
+// trace
+//
+// Solve every sudoku quietly, printing each solve as a line of JSON with every step it
+// took. See json.rs for what it looks like. Sudokus without just the one solution are
+// left out, and the exit code says so.
+#[cfg(feature = "json")]
+fn trace(input: &Input) -> Outcome {
+    let mut failure = None;
+    for (idx, sudoku) in read(input)?.sudokus.iter().enumerate() {
+        let checked = check_solutions(idx + 1, sudoku);
+        if checked.is_err() {
+            worst(checked, &mut failure);
+            continue;
+        }
+        println!("{}", json::trace_to_json(&rating::trace(sudoku)));
     }
-    return;
+    finish(failure)
 }
@@ -0,0 +1,635 @@
+/*
+ * Playing a sudoku full screen in the terminal.
+ *
+ * The grid is drawn either small with just the values, or big in the same layout
+ * as print_possibles with the pencil marks in every unsolved box. Pencil marks start
+ * out as whatever the file had, so a blank box has every value marked the same as
+ * print_possibles would show it.
+ *
+ * Keys:
+ *
+ * arrows        - move around the grid, wrapping at the edges
+ * 1-9           - put a value in the box, or in pencil mode mark or unmark it
+ * 0, backspace  - clear the box
+ * p             - switch pencil mode on and off
+ * c             - show or hide the pencil marks
+ * a             - mark every box with just the values nothing it can see holds
+ * h, ?          - a hint, press again for the whole step
+ * u, ctrl-z     - undo
+ * r, ctrl-y     - redo
+ * m             - mark the spot, like before trying a guess
+ * b             - go back to the spot marked
+ * q, esc        - quit
+ *
+ * Values that clash with another in the same row, column or cell are shown in red.
+ * The Game is kept apart from the terminal so it can be played by the tests.
+ */
+use crate::history::History;
+use crate::rating;
+use crate::sk_box::BLANK_BOX;
+use crate::sudoku::Sudoku;
+use crossterm::cursor::{self, MoveTo, MoveToNextLine};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, ContentStyle, PrintStyledContent, Stylize};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, Write};
+
+const KEYS: [&str; 2] = [
+    "arrows move  1-9 enter  0 clear  p pencil  c marks  a auto marks",
+    "h hint  u undo  r redo  m mark the spot  b back to it  q quit",
+];
+
+// A bit of text on the screen and how to draw it.
+pub type Span = (String, ContentStyle);
+
+/**
+ * Game
+ *
+ * Everything about a game in progress.
+ *
+ * puzzle     - The sudoku as loaded, any value in it can't be changed.
+ * solution   - What it should end up as, to say when it's done and to check the
+ *              values are right before giving a hint.
+ * history    - Every change made, to undo and redo. Its sudoku is the values
+ *              entered so far, with the pencil marks as the possible values of
+ *              unsolved boxes. Unlike other sudokus it can have values that clash
+ *              and boxes with no marks at all, so never check it.
+ * cursor     - The box the cursor is on, from 0 to 80.
+ * pencil     - Whether digits mark boxes rather than fill them in.
+ * candidates - Whether to draw the big grid with the pencil marks.
+ * message    - A line to show under the grid.
+ * highlight  - Boxes to pick out, like the ones a hint is about.
+ */
+pub struct Game {
+    pub puzzle: Sudoku,
+    pub solution: Sudoku,
+    pub history: History,
+    pub cursor: usize,
+    pub pencil: bool,
+    pub candidates: bool,
+    pub message: String,
+    pub highlight: Vec<usize>,
+    hinted: bool,
+}
+
+// The name of the spot kept by m, in the history's checkpoints.
+const SPOT: &str = "spot";
+
+// Boxes are named the usual way, like r1c2 for row 1 column 2.
+pub fn box_name(idx: usize) -> String {
+    format!("r{}c{}", idx / 9 + 1, idx % 9 + 1)
+}
+
+impl Game {
+    pub fn new(puzzle: Sudoku, solution: Sudoku) -> Game {
+        Game {
+            puzzle,
+            solution,
+            history: History::new(puzzle),
+            cursor: 0,
+            pencil: false,
+            candidates: false,
+            message: String::new(),
+            highlight: Vec::new(),
+            hinted: false,
+        }
+    }
+
+    /**
+     * grid
+     *
+     * The values entered so far and the pencil marks, see history above.
+     */
+    pub fn grid(&self) -> &Sudoku {
+        self.history.sudoku()
+    }
+
+    /**
+     * key
+     *
+     * Do whatever a key press does. Returns false once it's time to quit.
+     */
+    pub fn key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('c') if ctrl => return false,
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Char('y') if ctrl => self.redo(),
+            KeyCode::Up => self.move_by(0, 8),
+            KeyCode::Down => self.move_by(0, 1),
+            KeyCode::Left => self.move_by(8, 0),
+            KeyCode::Right => self.move_by(1, 0),
+            KeyCode::Char(digit @ '1'..='9') => self.enter(digit as u8 - b'0'),
+            KeyCode::Char('0') | KeyCode::Char('.') | KeyCode::Backspace | KeyCode::Delete => {
+                self.clear()
+            }
+            KeyCode::Char('p') => {
+                self.pencil = !self.pencil;
+                // No point marking what can't be seen.
+                if self.pencil {
+                    self.candidates = true;
+                }
+            }
+            KeyCode::Char('c') => self.candidates = !self.candidates,
+            KeyCode::Char('a') => self.auto_marks(),
+            KeyCode::Char('h') | KeyCode::Char('?') => self.hint(),
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('r') => self.redo(),
+            KeyCode::Char('m') => {
+                self.history.checkpoint(SPOT);
+                self.message = "Spot marked, b comes back here".to_string();
+            }
+            KeyCode::Char('b') => self.back(),
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            _ => {}
+        }
+        true
+    }
+
+    // Move the cursor, going right and down by the amounts given so adding 8 is the
+    // same as going back one.
+    fn move_by(&mut self, cols: usize, rows: usize) {
+        let col = (self.cursor % 9 + cols) % 9;
+        let row = (self.cursor / 9 + rows) % 9;
+        self.cursor = row * 9 + col;
+    }
+
+    fn given(&mut self) -> bool {
+        let given = self.puzzle.box_at(self.cursor).solved();
+        if given {
+            self.message = format!("{} is given", box_name(self.cursor));
+        }
+        given
+    }
+
+    fn enter(&mut self, value: u8) {
+        if self.given() {
+            return;
+        }
+        let idx = self.cursor;
+        let sk_box = *self.grid().box_at(idx);
+        let changed = if !self.pencil {
+            self.history.set_val(idx, value)
+        } else if sk_box.solved() {
+            self.message = format!("Clear {} to pencil in marks", box_name(idx));
+            false
+        } else if sk_box.is_poss(value) {
+            self.history.remove_possible_value(idx, value)
+        } else {
+            let mut marked = sk_box;
+            marked.poss[value as usize] = true;
+            self.history.set_box(idx, marked)
+        };
+        if changed {
+            self.changed();
+        }
+    }
+
+    fn clear(&mut self) {
+        if self.given() {
+            return;
+        }
+        if self.history.set_box(self.cursor, BLANK_BOX) {
+            self.changed();
+        }
+    }
+
+    fn auto_marks(&mut self) {
+        if self.history.auto_marks() {
+            self.changed();
+        }
+    }
+
+    fn undo(&mut self) {
+        match self.history.undo() {
+            Some(_) => self.changed(),
+            None => self.message = "Nothing to undo".to_string(),
+        }
+    }
+
+    fn redo(&mut self) {
+        match self.history.redo() {
+            Some(_) => self.changed(),
+            None => self.message = "Nothing to redo".to_string(),
+        }
+    }
+
+    // Undo or redo back to the spot marked with m.
+    fn back(&mut self) {
+        let from = self.history.position();
+        if self.history.restore(SPOT) {
+            self.changed();
+            if !self.solved() {
+                let boxes = self.history.diff(from, self.history.position()).len();
+                self.message = format!("Back to the spot marked, {} boxes put back", boxes);
+            }
+        } else {
+            self.message = "No spot marked to go back to".to_string();
+        }
+    }
+
+    // Anything shown about the grid before it changed is out of date now.
+    fn changed(&mut self) {
+        self.highlight.clear();
+        self.hinted = false;
+        self.message = if self.solved() {
+            "Solved!".to_string()
+        } else {
+            String::new()
+        };
+    }
+
+    pub fn solved(&self) -> bool {
+        self.grid().values() == self.solution.values()
+    }
+
+    /**
+     * hint
+     *
+     * Point out where the easiest technique that gets anywhere can be used, or
+     * with a second press show everything it does. Hints only go from the values
+     * entered, as the pencil marks could be anything, and only once every value is
+     * right.
+     */
+    pub fn hint(&mut self) {
+        let conflicts = self.grid().conflicts();
+        if !conflicts.is_empty() {
+            self.message = "Sort out the values in red first".to_string();
+            return;
+        }
+        let wrong: Vec<usize> = (0..81)
+            .filter(|&idx| {
+                let value = self.grid().box_at(idx).value;
+                value.is_some() && value != self.solution.box_at(idx).value
+            })
+            .collect();
+        if let Some(&first) = wrong.first() {
+            self.message = format!("{} isn't right", box_name(first));
+            self.highlight = wrong;
+            return;
+        }
+        if self.solved() {
+            self.message = "Already solved".to_string();
+            return;
+        }
+
+        match rating::next_step(&self.grid().auto_marks()) {
+            Some(step) => {
+                self.highlight = step
+                    .placed
+                    .iter()
+                    .chain(step.removed.iter())
+                    .map(|d| (d.row - 1) * 9 + d.col - 1)
+                    .collect();
+                self.highlight.dedup();
+                self.message = if self.hinted {
+                    step.to_string()
+                } else {
+                    format!("Try a {} here", step.technique)
+                };
+                self.hinted = true;
+            }
+            None => self.message = "No technique gets anywhere from here".to_string(),
+        }
+    }
+
+    // How to draw a box, from the least to the most important so the last one wins
+    // where they clash.
+    fn style(&self, idx: usize, conflicts: &[usize]) -> ContentStyle {
+        let mut style = ContentStyle::new();
+        if self.puzzle.box_at(idx).solved() {
+            style = style.bold();
+        } else if self.grid().box_at(idx).solved() {
+            style = style.with(Color::Blue);
+        } else {
+            style = style.with(Color::DarkGrey);
+        }
+        if self.highlight.contains(&idx) {
+            style = style.on(Color::Yellow).with(Color::Black);
+        }
+        if conflicts.contains(&idx) {
+            style = style.with(Color::Red);
+        }
+        if idx == self.cursor {
+            style = style.attribute(Attribute::Reverse);
+        }
+        style
+    }
+
+    // The 3 characters of one line of a box. The small grid is just the value, the
+    // big one is each of the 3 lines of the print_possibles layout.
+    fn box_text(&self, idx: usize, line: u8) -> String {
+        let sk_box = self.grid().box_at(idx);
+        if !self.candidates {
+            return format!(" {} ", sk_box.get_c());
+        }
+        (1..=3)
+            .map(|col| sk_box.get_pretty_c(line * 3 + col))
+            .collect()
+    }
+
+    /**
+     * screen
+     *
+     * Everything to draw, a line at a time, each made of bits of text in their own
+     * style.
+     */
+    pub fn screen(&self) -> Vec<Vec<Span>> {
+        let plain = |text: &str| (text.to_string(), ContentStyle::new());
+        let conflicts = self.grid().conflicts();
+        let lines_per_box = if self.candidates { 3 } else { 1 };
+        let mut screen = Vec::new();
+
+        screen.push(vec![plain("╔═══════════╦═══════════╦═══════════╗")]);
+        for row in 0..9 {
+            if row == 3 || row == 6 {
+                screen.push(vec![plain("╠═══════════╬═══════════╬═══════════╣")]);
+            }
+            for line in 0..lines_per_box {
+                let mut spans = vec![plain("║")];
+                for col in 0..9 {
+                    let idx = row * 9 + col;
+                    spans.push((self.box_text(idx, line), self.style(idx, &conflicts)));
+                    spans.push(plain(if col % 3 == 2 { "║" } else { "|" }));
+                }
+                screen.push(spans);
+            }
+            if self.candidates && row % 3 != 2 {
+                screen.push(vec![plain("║---+---+---║---+---+---║---+---+---║")]);
+            }
+        }
+        screen.push(vec![plain("╚═══════════╩═══════════╩═══════════╝")]);
+
+        let mode = if self.pencil { "Pencil" } else { "Digits" };
+        screen.push(vec![plain(&format!("{}  {}", box_name(self.cursor), mode))]);
+        screen.push(vec![(self.message.clone(), ContentStyle::new().bold())]);
+        for keys in KEYS {
+            screen.push(vec![(keys.to_string(), ContentStyle::new().dark_grey())]);
+        }
+        screen
+    }
+}
+
+/**
+ * FullScreen
+ *
+ * Takes the terminal over while it's around, and puts it back how it was when
+ * dropped, even if something panics. Shared with replay.rs.
+ */
+pub struct FullScreen;
+
+impl FullScreen {
+    pub fn new() -> io::Result<FullScreen> {
+        terminal::enable_raw_mode()?;
+        let full_screen = FullScreen;
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+        Ok(full_screen)
+    }
+
+    /**
+     * draw
+     *
+     * Draw over whatever was on screen before, a line at a time.
+     */
+    pub fn draw(&self, screen: Vec<Vec<Span>>) -> io::Result<()> {
+        let mut out = io::stdout();
+        queue!(out, MoveTo(0, 0))?;
+        for line in screen {
+            for (text, style) in line {
+                queue!(out, PrintStyledContent(style.apply(text)))?;
+            }
+            queue!(out, Clear(ClearType::UntilNewLine), MoveToNextLine(1))?;
+        }
+        queue!(out, Clear(ClearType::FromCursorDown))?;
+        out.flush()
+    }
+}
+
+impl Drop for FullScreen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/**
+ * run
+ *
+ * Take over the terminal and play until the player quits.
+ */
+pub fn run(mut game: Game) -> io::Result<()> {
+    let full_screen = FullScreen::new()?;
+    loop {
+        full_screen.draw(game.screen())?;
+        match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release && !game.key(key) => {
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitboard::BitBoard;
+
+    fn game(filename: &str) -> Game {
+        let puzzle = Sudoku::from_ss(filename.to_string()).unwrap();
+        let solution = BitBoard::from_sudoku(&puzzle).solutions(1)[0].to_sudoku();
+        Game::new(puzzle, solution)
+    }
+
+    fn press(game: &mut Game, code: KeyCode) -> bool {
+        game.key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn text(game: &Game) -> Vec<String> {
+        game.screen()
+            .iter()
+            .map(|line| line.iter().map(|(text, _)| text.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_move() {
+        let mut game = game("test/simple.ss");
+        press(&mut game, KeyCode::Left);
+        assert_eq!(game.cursor, 8);
+        press(&mut game, KeyCode::Up);
+        assert_eq!(game.cursor, 80);
+        press(&mut game, KeyCode::Down);
+        press(&mut game, KeyCode::Right);
+        press(&mut game, KeyCode::Right);
+        assert_eq!(game.cursor, 1);
+        assert!(!press(&mut game, KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn test_enter_undo_redo() {
+        let mut game = game("test/simple.ss");
+        assert!(!game.puzzle.box_at(0).solved());
+
+        press(&mut game, KeyCode::Char('4'));
+        assert_eq!(game.grid().box_at(0).value, Some(4));
+        press(&mut game, KeyCode::Char('7'));
+        press(&mut game, KeyCode::Backspace);
+        assert!(!game.grid().box_at(0).solved());
+
+        press(&mut game, KeyCode::Char('u'));
+        assert_eq!(game.grid().box_at(0).value, Some(7));
+        press(&mut game, KeyCode::Char('u'));
+        press(&mut game, KeyCode::Char('u'));
+        assert_eq!(*game.grid(), game.puzzle);
+        press(&mut game, KeyCode::Char('u'));
+        assert_eq!(game.message, "Nothing to undo");
+
+        game.key(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::CONTROL));
+        assert_eq!(game.grid().box_at(0).value, Some(4));
+
+        // A new change means there's nothing left to redo.
+        press(&mut game, KeyCode::Char('5'));
+        press(&mut game, KeyCode::Char('r'));
+        assert_eq!(game.message, "Nothing to redo");
+
+        // Givens can't be touched. r1c4 is a 2.
+        game.cursor = 3;
+        press(&mut game, KeyCode::Char('1'));
+        press(&mut game, KeyCode::Char('0'));
+        assert_eq!(game.grid().box_at(3).value, Some(2));
+        assert_eq!(game.message, "r1c4 is given");
+    }
+
+    #[test]
+    fn test_spot() {
+        let mut game = game("test/simple.ss");
+        press(&mut game, KeyCode::Char('b'));
+        assert_eq!(game.message, "No spot marked to go back to");
+
+        press(&mut game, KeyCode::Char('4'));
+        press(&mut game, KeyCode::Char('m'));
+        game.cursor = 1;
+        press(&mut game, KeyCode::Char('5'));
+        press(&mut game, KeyCode::Char('p'));
+        press(&mut game, KeyCode::Char('a'));
+        let before = *game.grid();
+        press(&mut game, KeyCode::Char('b'));
+        let put_back = (0..81)
+            .filter(|&idx| before.box_at(idx) != game.grid().box_at(idx))
+            .count();
+        assert!(put_back > 1);
+        assert_eq!(
+            game.message,
+            format!("Back to the spot marked, {} boxes put back", put_back)
+        );
+        assert_eq!(game.grid().box_at(0).value, Some(4));
+        assert_eq!(*game.grid().box_at(1), *game.puzzle.box_at(1));
+
+        // What was undone going back can still be redone.
+        press(&mut game, KeyCode::Char('r'));
+        assert_eq!(game.grid().box_at(1).value, Some(5));
+    }
+
+    #[test]
+    fn test_pencil_marks() {
+        let mut game = game("test/simple.ss");
+        press(&mut game, KeyCode::Char('p'));
+        assert!(game.pencil && game.candidates);
+
+        let marks = game.grid().box_at(0).get_possibles_bits();
+        press(&mut game, KeyCode::Char('4'));
+        assert_eq!(
+            game.grid().box_at(0).get_possibles_bits(),
+            marks & !(1 << 4)
+        );
+        press(&mut game, KeyCode::Char('4'));
+        assert_eq!(game.grid().box_at(0).get_possibles_bits(), marks);
+
+        // Marks never fill a box in, even down to the last one.
+        for digit in ['2', '3', '5', '7'] {
+            press(&mut game, KeyCode::Char(digit));
+        }
+        assert!(!game.grid().box_at(0).solved());
+        assert_eq!(game.grid().box_at(0).get_possibles(), vec![4]);
+
+        press(&mut game, KeyCode::Char('a'));
+        assert_eq!(*game.grid(), game.puzzle.auto_marks());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut game = game("test/simple.ss");
+        press(&mut game, KeyCode::Char('6'));
+        assert_eq!(game.grid().conflicts(), vec![0, 4, 9]);
+
+        let red = |idx: usize| {
+            let line = &game.screen()[1];
+            line[idx * 2 + 1].1.foreground_color == Some(Color::Red)
+        };
+        assert!(red(0) && red(4) && !red(1));
+
+        press(&mut game, KeyCode::Char('h'));
+        assert_eq!(game.message, "Sort out the values in red first");
+    }
+
+    #[test]
+    fn test_hint() {
+        let mut game = game("test/simple.ss");
+        press(&mut game, KeyCode::Char('h'));
+        assert_eq!(game.message, "Try a Single Position here");
+        assert!(!game.highlight.is_empty());
+        press(&mut game, KeyCode::Char('h'));
+        assert!(game.message.starts_with("Single Position: r"));
+
+        // A wrong value gets pointed out before anything else, even when it doesn't
+        // clash with anything yet.
+        let right = game.solution.box_at(0).value.unwrap() as u16;
+        let marks = game.puzzle.auto_marks().box_at(0).get_possibles();
+        let wrong = marks.into_iter().find(|&value| value != right).unwrap();
+        press(&mut game, KeyCode::Char((b'0' + wrong as u8) as char));
+        assert!(game.grid().conflicts().is_empty());
+        press(&mut game, KeyCode::Char('h'));
+        assert_eq!(game.message, "r1c1 isn't right");
+        assert_eq!(game.highlight, vec![0]);
+    }
+
+    #[test]
+    fn test_solved() {
+        let mut game = game("test/simple.ss");
+        let mut grid = game.solution;
+        grid.box_at_mut(0).set_val(1);
+        game.history = History::new(grid);
+        game.cursor = 0;
+        let value = game.solution.box_at(0).value.unwrap();
+        press(&mut game, KeyCode::Char((b'0' + value) as char));
+        assert_eq!(game.message, "Solved!");
+        press(&mut game, KeyCode::Char('h'));
+        assert_eq!(game.message, "Already solved");
+    }
+
+    #[test]
+    fn test_screen() {
+        let mut game = game("test/simple.ss");
+        let small = text(&game);
+        assert_eq!(small.len(), 13 + 4);
+        let mut row = "║".to_string();
+        for (col, value) in game.grid().to_line_string()[..9].chars().enumerate() {
+            row += &format!(" {} {}", value, if col % 3 == 2 { "║" } else { "|" });
+        }
+        assert_eq!(small[1], row);
+
+        // The big grid is the print_possibles layout exactly.
+        press(&mut game, KeyCode::Char('c'));
+        let big = text(&game);
+        let possibles: Vec<String> = game
+            .grid()
+            .to_possibles_string()
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(big[..possibles.len()], possibles[..]);
+        assert_eq!(big[possibles.len()], "r1c1  Digits");
+    }
+}
@@ -0,0 +1,338 @@
+/*
+ * Rating how hard a sudoku is by the hardest technique needed to solve it.
+ *
+ * The solve always uses the easiest technique that gets anywhere, going back to the
+ * start of the list after every bit of progress. That way a harder technique only
+ * counts if nothing easier would have done the job, which is roughly how a person
+ * would go about it.
+ */
+use crate::forcing;
+use crate::forcing::ForcingTechnique;
+use crate::solvers;
+use crate::sudoku::Sudoku;
+use std::fmt;
+
+/**
+ * Technique
+ *
+ * Every technique the solver knows, from easiest to hardest. The order matters as
+ * it's what ratings compare with.
+ */
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "json",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Technique {
+    SinglePosition,
+    NakedSet,
+    CandidateLine,
+    #[cfg_attr(feature = "json", serde(rename = "xwing"))]
+    XWing,
+    DigitNishio,
+    CellForcingChain,
+    UnitForcingChain,
+}
+
+pub const TECHNIQUES: [Technique; 7] = [
+    Technique::SinglePosition,
+    Technique::NakedSet,
+    Technique::CandidateLine,
+    Technique::XWing,
+    Technique::DigitNishio,
+    Technique::CellForcingChain,
+    Technique::UnitForcingChain,
+];
+
+impl fmt::Display for Technique {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Technique::SinglePosition => formatter.write_str("Single Position"),
+            Technique::NakedSet => formatter.write_str("Naked Set"),
+            Technique::CandidateLine => formatter.write_str("Candidate Line"),
+            Technique::XWing => formatter.write_str("X Wing"),
+            Technique::DigitNishio => ForcingTechnique::DigitNishio.fmt(formatter),
+            Technique::CellForcingChain => ForcingTechnique::CellForcingChain.fmt(formatter),
+            Technique::UnitForcingChain => ForcingTechnique::UnitForcingChain.fmt(formatter),
+        }
+    }
+}
+
+impl Technique {
+    /**
+     * from_name
+     *
+     * Short names for the command line, the same as the function names in
+     * solvers.rs and forcing.rs.
+     */
+    pub fn from_name(name: &str) -> Option<Technique> {
+        match name {
+            "single_position" => Some(Technique::SinglePosition),
+            "naked_set" => Some(Technique::NakedSet),
+            "candidate_line" => Some(Technique::CandidateLine),
+            "xwing" => Some(Technique::XWing),
+            "digit_nishio" => Some(Technique::DigitNishio),
+            "cell_forcing_chain" => Some(Technique::CellForcingChain),
+            "unit_forcing_chain" => Some(Technique::UnitForcingChain),
+            _ => None,
+        }
+    }
+
+    /**
+     * apply
+     *
+     * Run the technique once over the sudoku, returning whether it changed
+     * anything.
+     */
+    pub fn apply(self, sudoku: &mut Sudoku) -> bool {
+        let before = *sudoku;
+        match self {
+            Technique::SinglePosition => solvers::single_position(sudoku),
+            Technique::NakedSet => solvers::naked_set(sudoku),
+            Technique::CandidateLine => solvers::candidate_line(sudoku),
+            Technique::XWing => solvers::xwing(sudoku),
+            Technique::DigitNishio => apply_step(sudoku, forcing::digit_nishio(sudoku)),
+            Technique::CellForcingChain => apply_step(sudoku, forcing::cell_forcing_chain(sudoku)),
+            Technique::UnitForcingChain => apply_step(sudoku, forcing::unit_forcing_chain(sudoku)),
+        }
+        before != *sudoku
+    }
+}
+
+fn apply_step(sudoku: &mut Sudoku, step: Option<forcing::ForcingStep>) {
+    if let Some(step) = step {
+        step.apply(sudoku);
+    }
+}
+
+/**
+ * Rating
+ *
+ * The hardest technique a solve needed, and every technique that got used along
+ * the way in order from easiest to hardest.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub struct Rating {
+    pub hardest: Technique,
+    pub used: Vec<Technique>,
+}
+
+impl Rating {
+    pub fn uses(&self, technique: Technique) -> bool {
+        self.used.contains(&technique)
+    }
+}
+
+/**
+ * rate
+ *
+ * Solve a copy of the sudoku and say how hard it was, or None if it couldn't be
+ * solved at all.
+ */
+pub fn rate(sudoku: &Sudoku) -> Option<Rating> {
+    let mut sudoku = *sudoku;
+    let mut used: Vec<Technique> = Vec::new();
+
+    while !sudoku.solved() {
+        let technique = TECHNIQUES
+            .iter()
+            .copied()
+            .find(|technique| technique.apply(&mut sudoku))?;
+
+        if !used.contains(&technique) {
+            used.push(technique);
+        }
+    }
+
+    used.sort();
+    Some(Rating {
+        // Already solved counts as the easiest there is.
+        hardest: used.last().copied().unwrap_or(Technique::SinglePosition),
+        used,
+    })
+}
+
+/**
+ * Deduction
+ *
+ * A value at a box, with the column and row from 1 to 9 like get_box.
+ */
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct Deduction {
+    pub col: usize,
+    pub row: usize,
+    pub value: u8,
+}
+
+/**
+ * Step
+ *
+ * One go of a technique during a solve: the values it placed and the possible
+ * values it took out of boxes that are still unsolved afterwards.
+ */
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    pub technique: Technique,
+    pub placed: Vec<Deduction>,
+    pub removed: Vec<Deduction>,
+}
+
+impl Step {
+    // Work out what changed between two goes of the sudoku.
+    fn between(technique: Technique, before: &Sudoku, after: &Sudoku) -> Step {
+        let mut step = Step {
+            technique,
+            placed: Vec::new(),
+            removed: Vec::new(),
+        };
+        for change in before.diff(after) {
+            let (col, row) = (change.idx % 9 + 1, change.idx / 9 + 1);
+            if let Some(value) = change.placed {
+                step.placed.push(Deduction { col, row, value });
+            }
+            for value in change.removed {
+                step.removed.push(Deduction { col, row, value });
+            }
+        }
+        step
+    }
+}
+
+// Steps are shown the usual short way, r1c2=3 for a value placed and r2c4<>8 for a
+// possible value taken out.
+impl fmt::Display for Step {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}:", self.technique)?;
+        let placed = self
+            .placed
+            .iter()
+            .map(|d| format!("r{}c{}={}", d.row, d.col, d.value));
+        let removed = self
+            .removed
+            .iter()
+            .map(|d| format!("r{}c{}<>{}", d.row, d.col, d.value));
+        let changes: Vec<String> = placed.chain(removed).collect();
+        write!(formatter, " {}", changes.join(", "))
+    }
+}
+
+/**
+ * next_step
+ *
+ * The step the easiest technique that gets anywhere would take next, or None if
+ * none of them can do anything.
+ */
+pub fn next_step(sudoku: &Sudoku) -> Option<Step> {
+    let mut after = *sudoku;
+    let technique = TECHNIQUES
+        .iter()
+        .copied()
+        .find(|technique| technique.apply(&mut after))?;
+    Some(Step::between(technique, sudoku, &after))
+}
+
+/**
+ * Trace
+ *
+ * Everything that happened in a solve, so it can be shown or played back
+ * somewhere else. The steps go the same way as rate, always the easiest technique
+ * that gets anywhere.
+ */
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trace {
+    pub initial: Sudoku,
+    pub steps: Vec<Step>,
+    #[cfg_attr(feature = "json", serde(rename = "final"))]
+    pub result: Sudoku,
+    pub solved: bool,
+}
+
+pub fn trace(sudoku: &Sudoku) -> Trace {
+    let mut result = *sudoku;
+    let mut steps = Vec::new();
+
+    while !result.solved() {
+        let before = result;
+        match TECHNIQUES
+            .iter()
+            .copied()
+            .find(|technique| technique.apply(&mut result))
+        {
+            Some(technique) => steps.push(Step::between(technique, &before, &result)),
+            None => break,
+        }
+    }
+
+    Trace {
+        initial: *sudoku,
+        steps,
+        result,
+        solved: result.solved(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate() {
+        let sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        let rating = rate(&sudoku).unwrap();
+        assert_eq!(rating.used[0], Technique::SinglePosition);
+        assert!(rating.hardest < Technique::DigitNishio);
+
+        // Top95 #4 needs the forcing techniques.
+        let sudoku = Sudoku::from_txt("test/top95.txt".to_string())[3];
+        let rating = rate(&sudoku).unwrap();
+        assert!(rating.hardest >= Technique::DigitNishio);
+        assert!(rating.uses(rating.hardest));
+    }
+
+    #[test]
+    fn test_technique_names() {
+        let names = [
+            "single_position",
+            "naked_set",
+            "candidate_line",
+            "xwing",
+            "digit_nishio",
+            "cell_forcing_chain",
+            "unit_forcing_chain",
+        ];
+        for (name, technique) in names.iter().zip(TECHNIQUES) {
+            assert_eq!(Technique::from_name(name), Some(technique));
+        }
+        assert_eq!(Technique::from_name("guess"), None);
+        assert_eq!(Technique::XWing.to_string(), "X Wing");
+    }
+
+    #[test]
+    fn test_trace() {
+        let sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        let trace = trace(&sudoku);
+        assert!(trace.solved);
+        assert_eq!(trace.initial, sudoku);
+
+        // Playing the steps back over the start gets to the same place.
+        let mut replay = sudoku;
+        for step in trace.steps.iter() {
+            for placed in step.placed.iter() {
+                let (cell, idx) = Sudoku::col_row_to_cell_idx(placed.col, placed.row);
+                replay.cells[cell].boxes[idx].set_val(placed.value);
+            }
+            for removed in step.removed.iter() {
+                let (cell, idx) = Sudoku::col_row_to_cell_idx(removed.col, removed.row);
+                replay.cells[cell].boxes[idx].remove_possible_value(removed.value as u16);
+            }
+        }
+        assert_eq!(replay, trace.result);
+
+        let used: Vec<Technique> = trace.steps.iter().map(|step| step.technique).collect();
+        assert_eq!(rate(&sudoku).unwrap().hardest, *used.iter().max().unwrap());
+    }
+}
@@ -0,0 +1,267 @@
+/*
+ * Reading sudokus a line at a time from anything that implements BufRead, be it a
+ * file, stdin or something being decompressed on the way in. Only one line is held
+ * in memory at a time so it doesn't matter how big the input is.
+ */
+use crate::sudoku::Sudoku;
+use std::fmt;
+use std::io::BufRead;
+
+/**
+ * ParseError
+ *
+ * Why a line couldn't be read, along with the line number in the input (from 1) so
+ * it can be found again.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "line {}: {}", self.line, self.reason)
+    }
+}
+
+/**
+ * Record
+ *
+ * A sudoku read from a line, along with which line it was (from 1) and anything
+ * that came after it on the line like a rating or name.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub struct Record {
+    pub line: usize,
+    pub sudoku: Sudoku,
+    pub metadata: Option<String>,
+}
+
+// Reads the sudoku, and anything after it worth keeping, from one line of a file.
+pub type LineParser = fn(&str) -> Result<(Sudoku, Option<String>), &'static str>;
+
+/**
+ * RecordReader
+ *
+ * An iterator giving one record, or the error for it, per line of input. Blank lines
+ * and comment lines starting with '#' are skipped, but still counted so the line
+ * numbers match the original. Lines are read as one line sudokus unless given
+ * another parser, like for candidates, so every format with a sudoku a line skips
+ * the same lines.
+ */
+pub struct RecordReader<R: BufRead> {
+    reader: R,
+    parse: LineParser,
+    line: Vec<u8>,
+    line_number: usize,
+    finished: bool,
+}
+
+impl<R: BufRead> RecordReader<R> {
+    pub fn new(reader: R) -> RecordReader<R> {
+        RecordReader::with_parser(reader, Sudoku::parse_line_metadata)
+    }
+
+    pub fn with_parser(reader: R, parse: LineParser) -> RecordReader<R> {
+        RecordReader {
+            reader,
+            parse,
+            line: Vec::new(),
+            line_number: 0,
+            finished: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for RecordReader<R> {
+    type Item = Result<Record, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            self.line.clear();
+            self.line_number += 1;
+
+            match self.reader.read_until(b'\n', &mut self.line) {
+                // Reading 0 length data shows we've reached the end.
+                Ok(0) => self.finished = true,
+                Ok(_) => match parse_record(self.line_number, &self.line, self.parse) {
+                    Some(record) => return Some(record),
+                    None => continue,
+                },
+                // Can't trust anything after the input itself fails, so report it and
+                // stop.
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(ParseError {
+                        line: self.line_number,
+                        reason: error.to_string(),
+                    }));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/**
+ * parse_record
+ *
+ * The record on one line of input, as read by RecordReader, or None for a blank or
+ * comment line that's skipped. Comments can be indented. For anything that needs
+ * to know about every line, not just the ones with sudokus on.
+ */
+pub fn parse_record(
+    line_number: usize,
+    line: &[u8],
+    parse: LineParser,
+) -> Option<Result<Record, ParseError>> {
+    // Lines are read as bytes so one that isn't UTF-8 can be reported on its own
+    // without losing everything after it.
+    let line = match std::str::from_utf8(line) {
+        Ok(line) => line.trim_end_matches(['\n', '\r']),
+        Err(error) => {
+            return Some(Err(ParseError {
+                line: line_number,
+                reason: error.to_string(),
+            }))
+        }
+    };
+    if line.trim().is_empty() || line.trim_start().starts_with('#') {
+        return None;
+    }
+
+    Some(match parse(line) {
+        Ok((sudoku, metadata)) => Ok(Record {
+            line: line_number,
+            sudoku,
+            metadata,
+        }),
+        Err(reason) => Err(ParseError {
+            line: line_number,
+            reason: reason.to_string(),
+        }),
+    })
+}
+
+/**
+ * SudokuReader
+ *
+ * The same as RecordReader for when all that's wanted is the sudokus.
+ */
+pub struct SudokuReader<R: BufRead> {
+    records: RecordReader<R>,
+}
+
+impl<R: BufRead> SudokuReader<R> {
+    pub fn new(reader: R) -> SudokuReader<R> {
+        SudokuReader {
+            records: RecordReader::new(reader),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for SudokuReader<R> {
+    type Item = Result<Sudoku, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.records
+            .next()
+            .map(|record| record.map(|record| record.sudoku))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const LINE: &str =
+        "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+    #[test]
+    fn test_read_lines() {
+        let input = format!("# A comment\n{}\n\n{}\r\n  # Another\n{}", LINE, LINE, LINE);
+        let mut reader = RecordReader::new(Cursor::new(input));
+
+        let expected = Sudoku::parse_line(LINE).unwrap();
+        for line in [2, 4, 6] {
+            let record = reader.next().unwrap().unwrap();
+            assert_eq!(record.sudoku, expected);
+            assert_eq!(record.line, line);
+            assert_eq!(record.metadata, None);
+        }
+        assert_eq!(reader.next(), None);
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let clash = LINE.replacen('.', "4", 1);
+        let input = format!("{}\n123\n{}\n{}x\n", LINE, clash, &LINE[..80]);
+        let results: Vec<_> = SudokuReader::new(Cursor::new(input)).collect();
+
+        assert_eq!(results.len(), 4);
+        assert!(results[0].is_ok());
+        assert_eq!(
+            results[1],
+            Err(ParseError {
+                line: 2,
+                reason: "expected 81 boxes".to_string()
+            })
+        );
+        assert_eq!(
+            results[2].clone().unwrap_err().to_string(),
+            "line 3: value repeated"
+        );
+        assert_eq!(results[3].clone().unwrap_err().line, 4);
+    }
+
+    #[test]
+    fn test_read_bad_bytes() {
+        // A line that isn't UTF-8 is an error for that line, and reading carries on.
+        let mut input = format!("{}\n", LINE).into_bytes();
+        input.extend_from_slice(b"\xff\n");
+        input.extend_from_slice(LINE.as_bytes());
+        let results: Vec<_> = SudokuReader::new(Cursor::new(input)).collect();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert_eq!(results[1].clone().unwrap_err().line, 2);
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn test_read_file() {
+        let file = std::fs::File::open("test/top95.txt").unwrap();
+        let reader = SudokuReader::new(std::io::BufReader::new(file));
+        assert_eq!(reader.filter(|sudoku| sudoku.is_ok()).count(), 95);
+    }
+
+    #[test]
+    fn test_read_annotated() {
+        let file = std::fs::File::open("test/annotated.txt").unwrap();
+        let records: Vec<Record> = RecordReader::new(std::io::BufReader::new(file))
+            .map(|record| record.unwrap())
+            .collect();
+
+        // Every line is the first of top95 written a different way.
+        let expected = Sudoku::parse_line(LINE).unwrap();
+        assert_eq!(records.len(), 5);
+        assert!(records.iter().all(|record| record.sudoku == expected));
+
+        let metadata: Vec<Option<&str>> = records.iter().map(|r| r.metadata.as_deref()).collect();
+        assert_eq!(
+            metadata,
+            vec![
+                None,
+                Some("top95 #1"),
+                Some("rating 9.2"),
+                Some("Arto Inkala; hard"),
+                None
+            ]
+        );
+        assert_eq!(records[3].line, 6);
+    }
+}
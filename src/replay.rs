@@ -0,0 +1,554 @@
+/*
+ * Playing a solve back a step at a time, drawn in place in the terminal instead of
+ * the screens full of grids solve -v prints.
+ *
+ * Each step shows the sudoku just before it in the print_possibles layout, with the
+ * boxes making up the pattern the technique spotted shaded, the values it's about to
+ * place in green and the possible values it's about to take out in red.
+ *
+ * Keys:
+ *
+ * right, n      - next step
+ * left, b       - step back
+ * home, end     - the start or the end
+ * space, a      - play or pause
+ * +, -          - play faster or slower
+ * q, esc        - quit
+ */
+use crate::forcing;
+use crate::history::History;
+use crate::houses::*;
+use crate::play::{FullScreen, Span};
+use crate::rating::{self, Deduction, Step, Technique};
+use crate::sudoku::Sudoku;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, ContentStyle, Stylize};
+use std::io;
+use std::time::Duration;
+
+const KEYS: &str = "right next  left back  home/end start/end  space play  +/- speed  q quit";
+const FASTEST: Duration = Duration::from_millis(50);
+const SLOWEST: Duration = Duration::from_secs(10);
+
+fn box_idx(deduction: &Deduction) -> usize {
+    (deduction.row - 1) * 9 + deduction.col - 1
+}
+
+// Unsolved boxes out of some indexes that could still be the value.
+fn holders(sudoku: &Sudoku, indexes: &[usize], value: u8) -> Vec<usize> {
+    indexes
+        .iter()
+        .copied()
+        .filter(|&idx| !sudoku.box_at(idx).solved() && sudoku.box_at(idx).is_poss(value))
+        .collect()
+}
+
+/**
+ * pattern
+ *
+ * The boxes behind a step, worked out from the sudoku before and after it: the
+ * values that ruled out possible values for single position, the set of boxes for
+ * a naked set, the boxes in the cell for a candidate line, the 4 corners of an X
+ * wing and the boxes tried by a forcing technique. The solvers sweep the whole
+ * sudoku in one go so there can be several patterns in a step.
+ */
+pub fn pattern(step: &Step, before: &Sudoku, after: &Sudoku) -> Vec<usize> {
+    let mut result = Vec::new();
+
+    let forcing = match step.technique {
+        Technique::DigitNishio => forcing::digit_nishio(before),
+        Technique::CellForcingChain => forcing::cell_forcing_chain(before),
+        Technique::UnitForcingChain => forcing::unit_forcing_chain(before),
+        _ => None,
+    };
+    if let Some(forcing) = forcing {
+        for branch in forcing.branches.iter() {
+            result.push((branch.row - 1) * 9 + branch.col - 1);
+        }
+    }
+
+    if step.technique == Technique::SinglePosition {
+        result.extend(step.placed.iter().map(box_idx));
+    }
+
+    for removed in step.removed.iter() {
+        let idx = box_idx(removed);
+        let value = removed.value;
+        result.extend(match step.technique {
+            Technique::SinglePosition => PEERS[idx]
+                .iter()
+                .copied()
+                .filter(|&peer| after.box_at(peer).value == Some(value))
+                .collect(),
+            Technique::NakedSet => set_pattern(before, idx, value),
+            Technique::CandidateLine => line_pattern(before, idx, value),
+            Technique::XWing => xwing_pattern(before, idx, value),
+            _ => Vec::new(),
+        });
+    }
+
+    result.sort();
+    result.dedup();
+    result
+}
+
+// A set of N boxes in a house that value can be taken out of idx because of.
+// Either N values that can only go in N boxes including idx, so idx can't be
+// anything else, or N boxes not including idx that can only be N values between
+// them, value being one of them.
+fn set_pattern(before: &Sudoku, idx: usize, value: u8) -> Vec<usize> {
+    for &house in BOX_HOUSES[idx].iter() {
+        let unsolved: Vec<usize> = HOUSES[house]
+            .iter()
+            .copied()
+            .filter(|&other| !before.box_at(other).solved())
+            .collect();
+        let bits = |other: usize| before.box_at(other).get_possibles_bits();
+
+        for values in (2u16..1024).step_by(2) {
+            let count = values.count_ones() as usize;
+            if !(2..=4).contains(&count) || values & (1 << value) != 0 {
+                continue;
+            }
+            let boxes: Vec<usize> = unsolved
+                .iter()
+                .copied()
+                .filter(|&other| bits(other) & values != 0)
+                .collect();
+            if boxes.len() == count && boxes.contains(&idx) {
+                return boxes;
+            }
+        }
+
+        for subset in 0u16..(1 << unsolved.len()) {
+            let count = subset.count_ones() as usize;
+            if !(2..=4).contains(&count) {
+                continue;
+            }
+            let boxes: Vec<usize> = (0..unsolved.len())
+                .filter(|&n| subset & (1 << n) != 0)
+                .map(|n| unsolved[n])
+                .collect();
+            let values = boxes.iter().fold(0, |values, &other| values | bits(other));
+            if !boxes.contains(&idx)
+                && values.count_ones() as usize == count
+                && values & (1 << value) != 0
+            {
+                return boxes;
+            }
+        }
+    }
+    Vec::new()
+}
+
+// The boxes of a cell where value can only go along the row or column through idx.
+fn line_pattern(before: &Sudoku, idx: usize, value: u8) -> Vec<usize> {
+    for line in [ROW_HOUSE + idx / 9, COL_HOUSE + idx % 9] {
+        for cell in HOUSES[CELL_HOUSE..CELL_HOUSE + 9].iter() {
+            if cell.contains(&idx) {
+                continue;
+            }
+            let boxes = holders(before, cell, value);
+            if !boxes.is_empty() && boxes.iter().all(|other| HOUSES[line].contains(other)) {
+                return boxes;
+            }
+        }
+    }
+    Vec::new()
+}
+
+// Two rows where value can only go in the same two columns, one of them the column
+// idx is in, or the same the other way round.
+fn xwing_pattern(before: &Sudoku, idx: usize, value: u8) -> Vec<usize> {
+    for first in [ROW_HOUSE, COL_HOUSE] {
+        // Where a box is along a row, or down a column.
+        let across = |other: usize| {
+            if first == ROW_HOUSE {
+                other % 9
+            } else {
+                other / 9
+            }
+        };
+        let lines = &HOUSES[first..first + 9];
+        for (n, top) in lines.iter().enumerate() {
+            for bottom in lines[n + 1..].iter() {
+                if top.contains(&idx) || bottom.contains(&idx) {
+                    continue;
+                }
+                let top_boxes = holders(before, top, value);
+                let bottom_boxes = holders(before, bottom, value);
+                let top_across: Vec<usize> = top_boxes.iter().map(|&b| across(b)).collect();
+                let bottom_across: Vec<usize> = bottom_boxes.iter().map(|&b| across(b)).collect();
+                if top_across.len() == 2
+                    && top_across == bottom_across
+                    && top_across.contains(&across(idx))
+                {
+                    return [top_boxes, bottom_boxes].concat();
+                }
+            }
+        }
+    }
+    Vec::new()
+}
+
+/**
+ * Replay
+ *
+ * A solve to play back, with the sudoku before every step and after the last one.
+ *
+ * states   - states[n] is the sudoku before steps[n], the last is where it ended.
+ * position - Which step is showing, or the end once it's steps.len().
+ * playing  - Whether to move on by itself every delay.
+ */
+pub struct Replay {
+    pub states: Vec<Sudoku>,
+    pub steps: Vec<Step>,
+    pub patterns: Vec<Vec<usize>>,
+    pub position: usize,
+    pub playing: bool,
+    pub delay: Duration,
+}
+
+impl Replay {
+    pub fn new(sudoku: &Sudoku, delay: Duration) -> Replay {
+        let trace = rating::trace(sudoku);
+
+        // Going through the steps again gives the sudoku between each of them.
+        let mut history = History::new(trace.initial);
+        for step in trace.steps.iter() {
+            assert!(history.apply(step.technique));
+        }
+        assert!(*history.sudoku() == trace.result);
+        let states: Vec<Sudoku> = (0..=history.position()).map(|n| history.at(n)).collect();
+
+        let patterns = trace
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(n, step)| pattern(step, &states[n], &states[n + 1]))
+            .collect();
+
+        Replay {
+            states,
+            steps: trace.steps,
+            patterns,
+            position: 0,
+            playing: false,
+            delay,
+        }
+    }
+
+    // The step showing, if not at the end.
+    fn step(&self) -> Option<&Step> {
+        self.steps.get(self.position)
+    }
+
+    /**
+     * key
+     *
+     * Do whatever a key press does. Returns false once it's time to quit.
+     */
+    pub fn key(&mut self, key: KeyEvent) -> bool {
+        let end = self.steps.len();
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Right | KeyCode::Char('n') => self.position = (self.position + 1).min(end),
+            KeyCode::Left | KeyCode::Char('b') => {
+                self.playing = false;
+                self.position = self.position.saturating_sub(1);
+            }
+            KeyCode::Home => self.position = 0,
+            KeyCode::End => self.position = end,
+            KeyCode::Char(' ') | KeyCode::Char('a') => {
+                self.playing = !self.playing;
+                if self.playing && self.position == end {
+                    self.position = 0;
+                }
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => self.delay = (self.delay / 2).max(FASTEST),
+            KeyCode::Char('-') => self.delay = (self.delay * 2).min(SLOWEST),
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            _ => {}
+        }
+        true
+    }
+
+    /**
+     * tick
+     *
+     * Move on a step when playing, stopping at the end.
+     */
+    pub fn tick(&mut self) {
+        if self.position < self.steps.len() {
+            self.position += 1;
+        }
+        if self.position == self.steps.len() {
+            self.playing = false;
+        }
+    }
+
+    // How to draw one possible value of a box, or its value in the middle if it's
+    // solved.
+    fn style(&self, idx: usize, value: u8) -> ContentStyle {
+        let sk_box = self.states[self.position].box_at(idx);
+        let mut style = if sk_box.solved() {
+            ContentStyle::new().bold()
+        } else {
+            ContentStyle::new().dark_grey()
+        };
+
+        if let Some(step) = self.step() {
+            let at = |deductions: &[Deduction]| {
+                deductions
+                    .iter()
+                    .any(|d| box_idx(d) == idx && d.value == value)
+            };
+            if self.patterns[self.position].contains(&idx) {
+                style = style.on(Color::Yellow).with(Color::Black);
+            }
+            if at(&step.removed) {
+                style = style.with(Color::Red).bold();
+            }
+            if at(&step.placed) {
+                style = style.with(Color::Green).bold();
+            }
+        }
+        style
+    }
+
+    /**
+     * screen
+     *
+     * Everything to draw, a line at a time, the same as Game::screen.
+     */
+    pub fn screen(&self) -> Vec<Vec<Span>> {
+        let plain = |text: &str| (text.to_string(), ContentStyle::new());
+        let sudoku = &self.states[self.position];
+        let mut screen = Vec::new();
+
+        screen.push(vec![plain("╔═══════════╦═══════════╦═══════════╗")]);
+        for row in 0..9 {
+            if row == 3 || row == 6 {
+                screen.push(vec![plain("╠═══════════╬═══════════╬═══════════╣")]);
+            }
+            for line in 0..3 {
+                let mut spans = vec![plain("║")];
+                for col in 0..9 {
+                    let idx = row * 9 + col;
+                    for value in line * 3 + 1..=line * 3 + 3 {
+                        let c = sudoku.box_at(idx).get_pretty_c(value);
+                        spans.push((c.to_string(), self.style(idx, value)));
+                    }
+                    spans.push(plain(if col % 3 == 2 { "║" } else { "|" }));
+                }
+                screen.push(spans);
+            }
+            if row % 3 != 2 {
+                screen.push(vec![plain("║---+---+---║---+---+---║---+---+---║")]);
+            }
+        }
+        screen.push(vec![plain("╚═══════════╩═══════════╩═══════════╝")]);
+
+        let bold = ContentStyle::new().bold();
+        match self.step() {
+            Some(step) => {
+                let title = format!(
+                    "Step {} of {}: {}",
+                    self.position + 1,
+                    self.steps.len(),
+                    step.technique
+                );
+                screen.push(vec![(title, bold)]);
+                screen.push(vec![plain(&step.to_string())]);
+            }
+            None => {
+                let title = if sudoku.solved() {
+                    format!("Solved in {} steps", self.steps.len())
+                } else {
+                    format!("Stuck after {} steps", self.steps.len())
+                };
+                screen.push(vec![(title, bold)]);
+                screen.push(Vec::new());
+            }
+        }
+
+        let playing = if self.playing {
+            format!("Playing, a step every {}ms", self.delay.as_millis())
+        } else {
+            "Paused".to_string()
+        };
+        screen.push(vec![
+            plain(&format!("{}  ", playing)),
+            (
+                " pattern ".to_string(),
+                ContentStyle::new().on(Color::Yellow).with(Color::Black),
+            ),
+            plain(" "),
+            (
+                "placed".to_string(),
+                ContentStyle::new().with(Color::Green).bold(),
+            ),
+            plain(" "),
+            (
+                "removed".to_string(),
+                ContentStyle::new().with(Color::Red).bold(),
+            ),
+        ]);
+        screen.push(vec![(KEYS.to_string(), ContentStyle::new().dark_grey())]);
+        screen
+    }
+}
+
+/**
+ * run
+ *
+ * Take over the terminal and play the solve back until told to quit.
+ */
+pub fn run(mut replay: Replay) -> io::Result<()> {
+    let full_screen = FullScreen::new()?;
+    loop {
+        full_screen.draw(replay.screen())?;
+        if replay.playing && !event::poll(replay.delay)? {
+            replay.tick();
+            continue;
+        }
+        match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release && !replay.key(key) => {
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::BLANK_SUDOKU;
+
+    fn replay(filename: &str) -> Replay {
+        let sudoku = Sudoku::from_ss(filename.to_string()).unwrap();
+        Replay::new(&sudoku, Duration::from_millis(500))
+    }
+
+    fn press(replay: &mut Replay, code: KeyCode) -> bool {
+        replay.key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_replay_states() {
+        let replay = replay("test/xwing.ss");
+        assert_eq!(replay.states.len(), replay.steps.len() + 1);
+        assert!(replay.states[replay.steps.len()].solved());
+
+        // Every step takes something out or puts something in, and the pattern
+        // behind it can be found.
+        for (n, step) in replay.steps.iter().enumerate() {
+            assert!(!step.placed.is_empty() || !step.removed.is_empty());
+            assert!(!replay.patterns[n].is_empty(), "{}", step);
+        }
+    }
+
+    #[test]
+    fn test_xwing_pattern() {
+        let replay = replay("test/xwing.ss");
+        let n = replay
+            .steps
+            .iter()
+            .position(|step| step.technique == Technique::XWing)
+            .unwrap();
+
+        // Four corners, two rows and two columns between them.
+        let corners = &replay.patterns[n];
+        assert_eq!(corners.len(), 4);
+        let mut rows: Vec<usize> = corners.iter().map(|idx| idx / 9).collect();
+        let mut cols: Vec<usize> = corners.iter().map(|idx| idx % 9).collect();
+        rows.dedup();
+        cols.sort();
+        cols.dedup();
+        assert_eq!((rows.len(), cols.len()), (2, 2));
+    }
+
+    #[test]
+    fn test_candidate_line_pattern() {
+        // r1c4 and r1c6 are the only places for 4 in the top middle cell, so it
+        // can't go anywhere else along row 1.
+        let mut sudoku = BLANK_SUDOKU;
+        for idx in [4, 12, 13, 14, 21, 22, 23] {
+            sudoku.box_at_mut(idx).remove_possible_value(4);
+        }
+        let mut after = sudoku;
+        assert!(Technique::CandidateLine.apply(&mut after));
+        assert!(!after.box_at(0).is_poss(4));
+
+        let step = rating::next_step(&sudoku).unwrap();
+        assert_eq!(step.technique, Technique::CandidateLine);
+        assert_eq!(pattern(&step, &sudoku, &after), vec![3, 5]);
+    }
+
+    #[test]
+    fn test_replay_keys() {
+        let mut replay = replay("test/simple.ss");
+        let end = replay.steps.len();
+
+        press(&mut replay, KeyCode::Left);
+        assert_eq!(replay.position, 0);
+        press(&mut replay, KeyCode::Right);
+        press(&mut replay, KeyCode::Right);
+        assert_eq!(replay.position, 2.min(end));
+        press(&mut replay, KeyCode::End);
+        press(&mut replay, KeyCode::Right);
+        assert_eq!(replay.position, end);
+
+        // Playing from the end starts again, and stops by itself at the end.
+        press(&mut replay, KeyCode::Char(' '));
+        assert!(replay.playing);
+        assert_eq!(replay.position, 0);
+        for _ in 0..end {
+            replay.tick();
+        }
+        assert!(!replay.playing);
+        assert_eq!(replay.position, end);
+
+        press(&mut replay, KeyCode::Char('+'));
+        assert_eq!(replay.delay, Duration::from_millis(250));
+        press(&mut replay, KeyCode::Char('-'));
+        press(&mut replay, KeyCode::Char('-'));
+        assert_eq!(replay.delay, Duration::from_millis(1000));
+        assert!(!press(&mut replay, KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn test_replay_screen() {
+        let mut replay = replay("test/simple.ss");
+        let text = |replay: &Replay| -> Vec<String> {
+            replay
+                .screen()
+                .iter()
+                .map(|line| line.iter().map(|(text, _)| text.as_str()).collect())
+                .collect()
+        };
+
+        // The grid is the print_possibles layout of the sudoku before the step.
+        let screen = text(&replay);
+        let possibles = replay.states[0].to_possibles_string();
+        let grid: Vec<&str> = possibles.lines().collect();
+        assert_eq!(screen[..grid.len()], grid[..]);
+        assert!(screen[grid.len()].starts_with("Step 1 of "));
+        assert!(screen[grid.len()].ends_with(": Single Position"));
+
+        // Something gets drawn as about to be placed or taken out.
+        let marked = replay.screen().iter().flatten().any(|(_, style)| {
+            style.foreground_color == Some(Color::Red)
+                || style.foreground_color == Some(Color::Green)
+        });
+        assert!(marked);
+
+        press(&mut replay, KeyCode::End);
+        let screen = text(&replay);
+        assert_eq!(
+            screen[grid.len()],
+            format!("Solved in {} steps", replay.steps.len())
+        );
+    }
+}
@@ -0,0 +1,211 @@
+/*
+ * SadMan Sudoku files.
+ *
+ * .sdk files hold one puzzle, with lines of metadata at the top each starting with
+ * '#' and a letter saying what it is, then the grid itself under a [Puzzle] heading:
+ *
+ * #AArto Inkala
+ * #LHard
+ * [Puzzle]
+ * 4.....8.5
+ * .3.......
+ * ...
+ *
+ * .sdm files are just one puzzle per line with 0 for the blanks, which the line
+ * reader already copes with.
+ */
+use crate::sudoku::Sudoku;
+
+// The metadata codes SadMan uses.
+pub const AUTHOR: char = 'A';
+pub const DESCRIPTION: char = 'D';
+pub const COMMENT: char = 'C';
+pub const DATE: char = 'B';
+pub const SOURCE: char = 'S';
+pub const LEVEL: char = 'L';
+pub const URL: char = 'U';
+
+// What each code means, for showing to people.
+pub fn code_name(code: char) -> &'static str {
+    match code {
+        AUTHOR => "Author",
+        DESCRIPTION => "Description",
+        COMMENT => "Comment",
+        DATE => "Date",
+        SOURCE => "Source",
+        LEVEL => "Level",
+        URL => "URL",
+        _ => "Unknown",
+    }
+}
+
+/**
+ * SdkPuzzle
+ *
+ * A puzzle from a .sdk file along with its metadata, kept in the order it was in
+ * the file so writing it back out gives the same file.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub struct SdkPuzzle {
+    pub sudoku: Sudoku,
+    pub metadata: Vec<(char, String)>,
+}
+
+impl SdkPuzzle {
+    // The first bit of metadata with the code, e.g. get(AUTHOR).
+    #[cfg(test)]
+    pub fn get(&self, code: char) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /**
+     * parse
+     *
+     * Read the text of a .sdk file. Any sections after [Puzzle] (SadMan saves the
+     * state of a game part way through in some) are skipped.
+     */
+    pub fn parse(text: &str) -> Result<SdkPuzzle, &'static str> {
+        let mut metadata = Vec::new();
+        let mut grid = String::new();
+        let mut section: Option<&str> = None;
+
+        for line in text.lines() {
+            let line = line.trim_end();
+            if line.starts_with('[') {
+                section = Some(line);
+            } else if section.is_none() {
+                if let Some(rest) = line.strip_prefix('#') {
+                    let mut chars = rest.chars();
+                    match chars.next() {
+                        Some(code) => metadata.push((code, chars.as_str().to_string())),
+                        None => return Err("metadata line with no code"),
+                    }
+                } else if !line.is_empty() {
+                    // Older files have no [Puzzle] heading, just the grid.
+                    grid.push_str(line);
+                }
+            } else if section == Some("[Puzzle]") {
+                grid.push_str(line);
+            }
+        }
+
+        let sudoku = Sudoku::parse_line(&grid)?;
+        Ok(SdkPuzzle { sudoku, metadata })
+    }
+
+    /**
+     * to_sdk_string
+     *
+     * Write the puzzle back out the same as parse reads it.
+     */
+    pub fn to_sdk_string(&self) -> String {
+        let mut result = String::new();
+        for (code, value) in self.metadata.iter() {
+            result.push('#');
+            result.push(*code);
+            result.push_str(value);
+            result.push('\n');
+        }
+
+        result.push_str("[Puzzle]\n");
+        let line = self.sudoku.to_line_string();
+        for row in 0..9 {
+            result.push_str(&line[row * 9..row * 9 + 9]);
+            result.push('\n');
+        }
+        result
+    }
+}
+
+/**
+ * to_sdm_string
+ *
+ * Sudokus as the lines of a .sdm file.
+ */
+pub fn to_sdm_string(sudokus: &[Sudoku]) -> String {
+    let mut result = String::new();
+    for sudoku in sudokus {
+        result.push_str(&sudoku.to_line_string().replace('.', "0"));
+        result.push('\n');
+    }
+    result
+}
+
+impl Sudoku {
+    /**
+     * from_sdk / from_sdm
+     *
+     * Read SadMan files, like from_ss and from_txt.
+     */
+    #[cfg(test)]
+    pub fn from_sdk(filename: String) -> Result<SdkPuzzle, &'static str> {
+        match std::fs::read_to_string(filename) {
+            Ok(text) => SdkPuzzle::parse(&text),
+            Err(_) => Err("Could not read sdk file"),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn from_sdm(filename: String) -> Result<Vec<Sudoku>, crate::reader::ParseError> {
+        use crate::reader::{ParseError, SudokuReader};
+
+        let file = match std::fs::File::open(filename) {
+            Ok(file) => file,
+            Err(error) => {
+                return Err(ParseError {
+                    line: 0,
+                    reason: error.to_string(),
+                })
+            }
+        };
+        SudokuReader::new(std::io::BufReader::new(file)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_read_sdk() {
+        let puzzle = Sudoku::from_sdk("test/inkala.sdk".to_string()).unwrap();
+        assert_eq!(puzzle.get(AUTHOR), Some("Arto Inkala"));
+        assert_eq!(puzzle.get(LEVEL), Some("Hard"));
+        assert_eq!(puzzle.get(URL), None);
+        assert_eq!(
+            puzzle.sudoku,
+            Sudoku::from_txt("test/top95.txt".to_string())[0]
+        );
+
+        // The game state section at the end doesn't get mixed in.
+        assert_eq!(puzzle.metadata.len(), 4);
+    }
+
+    #[test]
+    fn test_sdk_round_trip() {
+        let text = fs::read_to_string("test/inkala.sdk").unwrap();
+        let puzzle = SdkPuzzle::parse(&text).unwrap();
+        let written = puzzle.to_sdk_string();
+
+        // Everything up to the game state comes back exactly.
+        assert!(text.starts_with(&written));
+        assert_eq!(SdkPuzzle::parse(&written), Ok(puzzle.clone()));
+
+        // No heading at all is fine too.
+        let bare = written.replace("[Puzzle]\n", "");
+        assert_eq!(SdkPuzzle::parse(&bare), Ok(puzzle));
+    }
+
+    #[test]
+    fn test_sdm_round_trip() {
+        let sudokus = Sudoku::from_sdm("test/top95.sdm".to_string()).unwrap();
+        assert_eq!(sudokus, Sudoku::from_txt("test/top95.txt".to_string()));
+
+        let text = fs::read_to_string("test/top95.sdm").unwrap();
+        assert_eq!(to_sdm_string(&sudokus), text);
+    }
+}
@@ -3,7 +3,14 @@ use std::fmt;
 
 // TODO: Change the from_possibles fucntions to use slices instead of vecs.
 
+// The JSON for a box is its value and list of possible values rather than the
+// array of flags, see json.rs.
 #[derive(PartialEq, Copy, Clone)]
+#[cfg_attr(
+    feature = "json",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "crate::json::BoxJson", try_from = "crate::json::BoxJson")
+)]
 pub struct Box {
     pub value: Option<u8>,
     pub poss: [bool; 10],
@@ -27,7 +34,7 @@ const BOX_EMPTY_POSS: [bool; 10] = [
     false, false, false, false, false, false, false, false, false, false,
 ];
 
-const POSSIBLE_BIT_MASK: u16 = 0b1111111110;
+pub const POSSIBLE_BIT_MASK: u16 = 0b1111111110;
 
 impl Box {
     /**
@@ -63,7 +70,7 @@ impl Box {
      *
      * Create a new box without a known value, from with a known set of possible values.
      */
-    #[allow(dead_code)]
+    #[cfg(test)]
     pub fn from_possibles(possibles: Vec<u8>) -> Box {
         let mut new_box = BLANK_BOX;
         new_box.set_possibles(possibles);
@@ -93,7 +100,7 @@ impl Box {
      */
     pub fn invert_possible_bits(possible_bits: u16) -> u16 {
         let mut inverted_bits = !possible_bits;
-        inverted_bits = inverted_bits & POSSIBLE_BIT_MASK;
+        inverted_bits &= POSSIBLE_BIT_MASK;
         inverted_bits
     }
 
@@ -126,7 +133,6 @@ impl Box {
      *
      * Create a new box without a known value, from with a known set of possible values.
      */
-    #[allow(dead_code)]
     pub fn from_possibles_bits(possibles: u16) -> Box {
         let mut new_box = BLANK_BOX;
         new_box.set_possibles_bits(possibles);
@@ -141,8 +147,9 @@ impl Box {
      * Note that setting a *single* possibility implicitly sets that possibility
      * as the value for this box.
      */
+    #[cfg(test)]
     pub fn set_possibles(&mut self, possibles: Vec<u8>) {
-        assert!(possibles.len() > 0);
+        assert!(!possibles.is_empty());
         assert!(possibles.len() <= 9);
         match possibles.len() {
             // If just a single value revert to setting that value as if it was a flat out set.
@@ -150,7 +157,7 @@ impl Box {
             // If a list set us back to 0 and set true for only those values we're given.
             _ => {
                 // Should not have value know if we're setting possibles! Can't go backwards.
-                assert!(self.value == None);
+                assert!(self.value.is_none());
                 self.poss = BOX_EMPTY_POSS;
                 for x in possibles {
                     self.poss[x as usize] = true;
@@ -191,14 +198,14 @@ impl Box {
                 0b10000000 => self.set_val(7),
                 0b100000000 => self.set_val(8),
                 0b1000000000 => self.set_val(9),
-                _ => assert!(false),
+                _ => unreachable!(),
             }
         } else {
             // Otherwise there are multiple possible values here. Iterate over them
             let mut n = 0;
             while n <= 9 {
                 self.poss[n] = (possibles >> n & 0b1) == 0b1;
-                n = n + 1;
+                n += 1;
             }
         }
     }
@@ -269,7 +276,7 @@ impl Box {
             }
             None => {
                 // Check with no confirmed value is that "0" is not a possible value.
-                assert!(self.poss[0] == false);
+                assert!(!self.poss[0]);
 
                 // Check that there is at least one index of the array of possible values that is positive.
                 let mut found_true = false;
@@ -285,7 +292,7 @@ impl Box {
     pub fn get_c(&self) -> char {
         match self.value {
             // Little hacky but 48 is '0' in ascii
-            Some(x) => ('0' as u8 + x) as char,
+            Some(x) => (b'0' + x) as char,
             None => '.',
         }
     }
@@ -305,12 +312,12 @@ impl Box {
     pub fn get_pretty_c(&self, value: u8) -> char {
         if self.solved() {
             if value == 5 {
-                ('0' as u8 + self.value.unwrap()) as char
+                (b'0' + self.value.unwrap()) as char
             } else {
                 ' '
             }
         } else if self.is_poss(value) {
-            ('0' as u8 + value) as char
+            (b'0' + value) as char
         } else {
             '.'
         }
@@ -318,12 +325,8 @@ impl Box {
 
     pub fn solved(&self) -> bool {
         match self.value {
-            Some(_x) => {
-                return true;
-            }
-            None => {
-                return false;
-            }
+            Some(_x) => true,
+            None => false,
         }
     }
 
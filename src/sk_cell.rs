@@ -2,6 +2,7 @@ use crate::sk_box::*;
 use std::fmt;
 
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cell {
     pub boxes: [Box; 9],
 }
@@ -27,22 +28,6 @@ impl fmt::Display for Cell {
 }
 
 impl Cell {
-    /**
-     * get_mut
-     *
-     * Just like the get_row_mut and get_cell_mut functions of the overall
-     * sudoku gets a vector of mutable boxes from the sudoku that solving functions
-     * that work on sets of 9 boxes can run over.
-     */
-    pub fn get_mut<'a>(&'a mut self) -> Vec<&mut Box> {
-        let mut result = Vec::new();
-        for sk_box in self.boxes.iter_mut() {
-            result.push(sk_box);
-        }
-
-        result
-    }
-
     /**
      * set
      *
@@ -52,12 +37,11 @@ impl Cell {
      * Used for intialising cells during sudoku construction so doesn't
      * set any possible values or consider whether cell is valid internally.
      */
-    #[allow(dead_code)]
+    #[cfg(test)]
     pub fn set(&mut self, values: [u8; 9]) {
         assert_eq!(values.len(), 9);
-        for x in 0..9 {
-            let i = values[x];
-            self.boxes[x] = Box::from_val(i);
+        for (sk_box, &value) in self.boxes.iter_mut().zip(values.iter()) {
+            *sk_box = Box::from_val(value);
         }
     }
 
@@ -129,7 +113,7 @@ impl Cell {
             }
         }
 
-        return true;
+        true
     }
 
     pub fn check(&self) {
@@ -137,10 +121,7 @@ impl Cell {
     }
 }
 
-// Note - uses a mut type because most callers have an set of mut values to work with
-// adn wecan't cast from mut to non-mut
-//
-// And to use this in the combo value later it needs to return an array, that
+// To use this in the combo value later it needs to return an array, that
 // has the format of being an array of possible values, 9 long, but with 0s
 // at the end if less than 9 numbers are possible.
 //
@@ -149,7 +130,7 @@ impl Cell {
 //
 // I KNOW THIS LOOKS BAD! But it sort of makes sense for the caller and how it's
 // used.
-pub fn unsolved_values(input: &Vec<&mut Box>) -> ([u8; 9], usize) {
+pub fn unsolved_values(input: &[Box; 9]) -> ([u8; 9], usize) {
     let mut solved: [bool; 10] = [false; 10];
     let mut result: [u8; 9] = [0; 9];
 
@@ -161,8 +142,8 @@ pub fn unsolved_values(input: &Vec<&mut Box>) -> ([u8; 9], usize) {
         .for_each(|x| solved[x.get_value().expect("Not a real value") as usize] = true);
 
     let mut j: usize = 0;
-    for i in 1..=9 {
-        if solved[i] == false {
+    for (i, &is_solved) in solved.iter().enumerate().skip(1) {
+        if !is_solved {
             result[j] = i as u8;
             j += 1;
         }
@@ -200,9 +181,9 @@ pub fn array_check(validate: [Box; 9], strict: bool) {
             None => {
                 // If the box just has possibles, tick them off as being available
                 // in the line.
-                for x in 1..10 {
-                    if sk_box.poss[x] {
-                        poss_found[x] = true;
+                for (found, &poss) in poss_found.iter_mut().zip(sk_box.poss.iter()).skip(1) {
+                    if poss {
+                        *found = true;
                     }
                 }
             }
@@ -210,7 +191,7 @@ pub fn array_check(validate: [Box; 9], strict: bool) {
                 // If the box has a value tick it off as found, and make sure it
                 // has not been seen before.
                 let idx = usize::from(found_val);
-                assert!(vals_found[idx] == false);
+                assert!(!vals_found[idx]);
                 vals_found[idx] = true;
             }
         }
@@ -230,13 +211,14 @@ pub fn array_check(validate: [Box; 9], strict: bool) {
 mod tests {
     use super::*;
     use crate::constants::*;
+    use crate::houses::CELL_HOUSE;
     use crate::solvers::*;
-    use crate::sudoku::Sudoku;
+    use crate::sudoku::{Sudoku, BLANK_SUDOKU};
 
     #[test]
     // Check that we can solve a box when there's only one value left.
     fn test_last_value_box() {
-        let mut test_cell: Cell = Cell {
+        let test_cell: Cell = Cell {
             boxes: [
                 BLANK_BOX,
                 Box::from_val(2),
@@ -250,9 +232,11 @@ mod tests {
             ],
         };
 
-        single_position_array(test_cell.get_mut());
+        let mut sudoku = BLANK_SUDOKU;
+        sudoku.cells[0] = test_cell;
+        single_position_house(&mut sudoku, CELL_HOUSE);
 
-        assert!(test_cell.boxes[TOP_LFT].value == Some(1));
+        assert!(sudoku.cells[0].boxes[TOP_LFT].value == Some(1));
     }
 
     #[test]
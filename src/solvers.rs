@@ -1,5 +1,6 @@
 use crate::constants::*;
-use crate::sk_box::Box;
+use crate::houses::*;
+use crate::sk_box::*;
 use crate::sk_cell;
 use crate::sudoku::Sudoku;
 
@@ -12,16 +13,16 @@ use crate::sudoku::Sudoku;
 // useful enum sometimes for switching on solving
 #[derive(PartialEq, Debug, Copy, Clone)]
 enum Direction {
-    HOR,
-    VER,
+    Hor,
+    Ver,
 }
 
 // Useful enum for how many times a value has been found
 #[derive(PartialEq, Debug, Copy, Clone)]
 enum Found {
-    NONE,
-    ONCE,
-    MANY,
+    None,
+    Once,
+    Many,
 }
 
 #[derive(PartialEq, Debug, Copy, Clone)]
@@ -32,7 +33,7 @@ struct PossValWhere {
 
 const BLANK_PVW: PossValWhere = PossValWhere {
     index: None,
-    found: Found::NONE,
+    found: Found::None,
 };
 
 /**
@@ -41,7 +42,7 @@ const BLANK_PVW: PossValWhere = PossValWhere {
  */
 pub fn normalise(sudoku: &mut Sudoku) {
     for i in 0..9 {
-        single_position_array(sudoku.cells[i].get_mut());
+        single_position_house(sudoku, CELL_HOUSE + i);
     }
 }
 
@@ -58,18 +59,9 @@ pub fn normalise(sudoku: &mut Sudoku) {
  *    set that box to the value. (Single position candidate)
  */
 pub fn single_position(sudoku: &mut Sudoku) {
-    for i in 0..9 {
-        // How does this not need a mut???
-        single_position_array(sudoku.get_row_mut(i));
-    }
-
-    for i in 0..9 {
-        // How does this not need a mut???
-        single_position_array(sudoku.get_col_mut(i));
-    }
-
-    for i in 0..9 {
-        single_position_array(sudoku.cells[i].get_mut());
+    // Rows, then columns, then cells.
+    for house in 0..27 {
+        single_position_house(sudoku, house);
     }
     sudoku.check();
 }
@@ -83,22 +75,38 @@ pub fn single_position(sudoku: &mut Sudoku) {
  */
 pub fn naked_set(sudoku: &mut Sudoku) {
     for i in 0..9 {
-        naked_set_array(sudoku.cells[i].get_mut());
+        naked_set_house(sudoku, CELL_HOUSE + i);
     }
 
     for i in 0..9 {
-        // How does this not need a mut???
-        naked_set_array(sudoku.get_row_mut(i));
+        naked_set_house(sudoku, ROW_HOUSE + i);
     }
 
     for i in 0..9 {
-        // How does this not need a mut???
-        naked_set_array(sudoku.get_col_mut(i));
+        naked_set_house(sudoku, COL_HOUSE + i);
     }
 }
 
 /**
- * single_position_array
+ * single_position_house / naked_set_house
+ *
+ * Run the array versions of the solvers over one house of the sudoku, by copying
+ * the house out and back in again.
+ */
+pub fn single_position_house(sudoku: &mut Sudoku, house: usize) {
+    let mut boxes = sudoku.get_house(house);
+    single_position_boxes(&mut boxes);
+    sudoku.set_house(house, boxes);
+}
+
+pub fn naked_set_house(sudoku: &mut Sudoku, house: usize) {
+    let mut boxes = sudoku.get_house(house);
+    naked_set_boxes(&mut boxes);
+    sudoku.set_house(house, boxes);
+}
+
+/**
+ * single_position_boxes
  *
  * This is the dumbest solving algorithim there is.
  *
@@ -110,7 +118,7 @@ pub fn naked_set(sudoku: &mut Sudoku) {
  * When run over every cell, row, and column it implmeents the single Position
  * and single_candidate logic.
  */
-pub fn single_position_array(mut boxes: Vec<&mut Box>) {
+fn single_position_boxes(boxes: &mut [Box; 9]) {
     // pos_vals is the bit mask of still possible values in this set of interlinked
     // boxes.
     //
@@ -136,18 +144,16 @@ pub fn single_position_array(mut boxes: Vec<&mut Box>) {
     for x in boxes.iter() {
         // If we have an actual value we blank out that possible value from the map
         // otherwise ignore the uncionfirmed values.
-        match x.value {
-            // mask it off against the inverse of the found value.
-            Some(confirmed_value) => poss_vals &= !(ON << confirmed_value),
-            None => (),
-        };
+        // mask it off against the inverse of the found value.
+        if let Some(confirmed_value) = x.value {
+            poss_vals &= !(ON << confirmed_value);
+        }
     }
 
     // Now in poss_vals we have an bitmap that represents all the values that nothing
     // else can be. So we apply that to each of the values in the 9 array
     // set that are still looking for a value.
-    for cur_idx in 0..9 {
-        let unsolved_box = &mut boxes[cur_idx];
+    for (cur_idx, unsolved_box) in boxes.iter_mut().enumerate() {
         // If we have an unconfirmed values remove the possibilities foumd, otherwise
         // for solved boxes we just skip over.
         match unsolved_box.value {
@@ -157,16 +163,16 @@ pub fn single_position_array(mut boxes: Vec<&mut Box>) {
 
                 for poss_val in unsolved_box.get_possibles() {
                     let lpv = &mut last_poss_vals[poss_val as usize];
-                    match (*lpv).found {
-                        Found::NONE => {
-                            lpv.found = Found::ONCE;
+                    match lpv.found {
+                        Found::None => {
+                            lpv.found = Found::Once;
                             lpv.index = Some(cur_idx as u8);
                         }
-                        Found::ONCE => {
-                            lpv.found = Found::MANY;
+                        Found::Once => {
+                            lpv.found = Found::Many;
                             lpv.index = None;
                         }
-                        Found::MANY => {}
+                        Found::Many => {}
                     }
                 }
             }
@@ -175,10 +181,8 @@ pub fn single_position_array(mut boxes: Vec<&mut Box>) {
 
     // We are now done interating over the boxes, and can check the LPV
     // array for any elements that have only been found once.
-    for cur_val in 1..=9 {
-        let lpv = last_poss_vals[cur_val];
-
-        if lpv.found == Found::ONCE {
+    for (cur_val, &lpv) in last_poss_vals.iter().enumerate().skip(1) {
+        if lpv.found == Found::Once {
             // We have a value that had been found once! The LPV will tell us the index in the
             // boxes.
             boxes[(lpv.index.unwrap()) as usize].set_val(cur_val as u8);
@@ -187,7 +191,7 @@ pub fn single_position_array(mut boxes: Vec<&mut Box>) {
 }
 
 /**
- * naked_set_array
+ * naked_set_boxes
  *
  * Looks for combinations of boxes where there are only X boxes that can
  * only be a set combination of X values, then remove that possible combination
@@ -200,7 +204,7 @@ pub fn single_position_array(mut boxes: Vec<&mut Box>) {
  * Groups of 5 or 6 are possible, but so rare and computationally expensive we don't bother.
  * https://www.sudokuoftheday.com/techniques/naked-pairs-triples/
  */
-fn naked_set_array(mut boxes: Vec<&mut Box>) {
+fn naked_set_boxes(boxes: &mut [Box; 9]) {
     /*
      * The logic we will follow for this function is as follows:
      *  - Iterate over every number of factorials we'll look for 2, 3, and 4
@@ -211,16 +215,15 @@ fn naked_set_array(mut boxes: Vec<&mut Box>) {
      *
      * For convenience of checkign we'll primarially use bit patterns, and arrays of bit patterns.
      */
-    assert!(boxes.len() == 9);
 
     // Filter out solved values in this collection of 9. The tick off the alogorithim and give
     // false positives (and add to the cost of the operation because that combo function adds up
     // fast!)
-    let (unsolved_values, len) = sk_cell::unsolved_values(&boxes);
+    let (unsolved_values, len) = sk_cell::unsolved_values(boxes);
 
     // TODO: Add logic to sometimes apply up to 5 factors if stuck - but it's overkill
     // everytime with current test set,
-    for factors in vec![2, 3, 4] {
+    for factors in [2, 3, 4] {
         let bit_patterns = combo(&unsolved_values[0..len], factors);
 
         for pattern in bit_patterns.iter() {
@@ -324,32 +327,32 @@ struct ConstantLine {
 const CONSTANT_LINES: [ConstantLine; 6] = [
     ConstantLine {
         bit_pattern: 0b0000000111,
-        direction: Direction::HOR,
+        direction: Direction::Hor,
         index: 0,
     },
     ConstantLine {
         bit_pattern: 0b0000111000,
-        direction: Direction::HOR,
+        direction: Direction::Hor,
         index: 1,
     },
     ConstantLine {
         bit_pattern: 0b0111000000,
-        direction: Direction::HOR,
+        direction: Direction::Hor,
         index: 2,
     },
     ConstantLine {
         bit_pattern: 0b0001001001,
-        direction: Direction::VER,
+        direction: Direction::Ver,
         index: 0,
     },
     ConstantLine {
         bit_pattern: 0b0010010010,
-        direction: Direction::VER,
+        direction: Direction::Ver,
         index: 1,
     },
     ConstantLine {
         bit_pattern: 0b0100100100,
-        direction: Direction::VER,
+        direction: Direction::Ver,
         index: 2,
     },
 ];
@@ -390,9 +393,7 @@ pub fn candidate_line(sudoku: &mut Sudoku) {
 
         // Now for each value of 0-9 see if it matches one of our masks
         // for that value only being possible in a given row or column
-        for candidate_value in 1..10 {
-            let value_bitmap = possibles[candidate_value];
-
+        for (candidate_value, &value_bitmap) in possibles.iter().enumerate().skip(1) {
             // Skip those values where there's only one possible location
             // these are easier/faster to catch with out naive nethods and
             // complicate debugging.
@@ -415,7 +416,7 @@ pub fn candidate_line(sudoku: &mut Sudoku) {
                     // We have found a candidate line! the candidate_value by matching
                     // the checkbitmap and only the check bitmap must be only in one row
                     // and/or column.
-                    if checkline.direction == Direction::HOR {
+                    if checkline.direction == Direction::Hor {
                         // Confirmed we have a candidate line identified as a horizontal row, so
                         // need to find the index of the two cells next to this one first.
                         //
@@ -435,7 +436,7 @@ pub fn candidate_line(sudoku: &mut Sudoku) {
                                     .rm_poss_from_row(candidate_value as u16, checkline.index);
                             }
                         }
-                    } else if checkline.direction == Direction::VER {
+                    } else if checkline.direction == Direction::Ver {
                         // Confirmed we have a candidate line identified as a horizontal row, so
                         // need to find the index of the two cells next to this one first.
                         //
@@ -489,15 +490,15 @@ pub fn xwing(sudoku: &mut Sudoku) {
             let mut found_right = false;
 
             // Now check each box in the row for possibly being that value.
-            for cur_col_idx in 0..9 {
-                if row[cur_col_idx].is_poss(poss_val) {
+            for (cur_col_idx, sk_box) in row.iter().enumerate() {
+                if sk_box.is_poss(poss_val) {
                     // Found a possible value. If it's the first find set the left_col_idx of
                     // the X wing, if it's the second (as known by having left set) set the right
                     // col_idx, but if it's the third it can't be used for an xwing so break.
-                    if found_left == false {
+                    if !found_left {
                         left_col_idx = cur_col_idx;
                         found_left = true;
-                    } else if found_right == false {
+                    } else if !found_right {
                         right_col_idx = cur_col_idx;
                         found_right = true;
                     } else {
@@ -526,21 +527,16 @@ pub fn xwing(sudoku: &mut Sudoku) {
             // are stacked on top of each other sharing a mid-layer will also be found by just
             // looking down.
 
-            println!(
-                "Found top element of X wing on row {}, value {} on colums {} and {}",
-                top_row_idx, poss_val, left_col_idx, right_col_idx
-            );
-
             'bot_row_loop: for bot_row_idx in (cur_row_idx + 1)..9 {
                 let bot_row = sudoku.get_row(bot_row_idx);
 
-                for cur_col_idx in 0..9 {
+                for (cur_col_idx, sk_box) in bot_row.iter().enumerate() {
                     // If the possible value is only possible in the left and right col_idxs
                     // already found for the top of the x wing then we have a match. So we check
                     // every col of thius row making sure it's either not possible if the col_idx
                     // isn't left/right one, or is possible in the left/right.
 
-                    if bot_row[cur_col_idx].is_poss(poss_val)
+                    if sk_box.is_poss(poss_val)
                         && ((cur_col_idx != left_col_idx) && (cur_col_idx != right_col_idx))
                     {
                         // If here we found the possible value in this row at the col indx
@@ -549,7 +545,7 @@ pub fn xwing(sudoku: &mut Sudoku) {
                         continue 'bot_row_loop;
                     }
 
-                    if !bot_row[cur_col_idx].is_poss(poss_val)
+                    if !sk_box.is_poss(poss_val)
                         && ((cur_col_idx == left_col_idx) || (cur_col_idx == right_col_idx))
                     {
                         // If here we found the possible value in this row at the col indx
@@ -559,11 +555,6 @@ pub fn xwing(sudoku: &mut Sudoku) {
                     }
                 }
 
-                println!(
-                    "Found bottom element of X wing on row {}, value {} on colums {} and {}",
-                    bot_row_idx, poss_val, left_col_idx, right_col_idx
-                );
-
                 // If we arrived here hallejlujah we have found an X wing! top_row_idx and
                 // bot_row_idx says which rows it is on, and left_col_idx and right_col_idx say which
                 // columsn it is on.
@@ -571,11 +562,11 @@ pub fn xwing(sudoku: &mut Sudoku) {
                 // So for the payoff we can remove the possible value of the X wing from every box
                 // in the matched rows except for the xwing locations itself.
                 for col_idx in [left_col_idx, right_col_idx] {
-                    let mut col = sudoku.get_col_mut(col_idx);
-
-                    for idx in 0..9 {
-                        if !(idx == top_row_idx || idx == bot_row_idx) {
-                            col[idx].remove_possible_value(poss_val as u16);
+                    for (row_idx, &idx) in HOUSES[COL_HOUSE + col_idx].iter().enumerate() {
+                        if !(row_idx == top_row_idx || row_idx == bot_row_idx) {
+                            sudoku
+                                .box_at_mut(idx)
+                                .remove_possible_value(poss_val as u16);
                         }
                     }
                 }
@@ -589,73 +580,58 @@ mod tests {
     // Inherit everything from up a level so we can run functions from there.
     use super::*;
     use crate::sk_box::BLANK_BOX;
+    use crate::sudoku::BLANK_SUDOKU;
+
+    // Put the 9 boxes in the top row of a blank sudoku, run the solver over that row
+    // and give back what's left of them.
+    fn run_on_row(boxes: [Box; 9], solver: fn(&mut Sudoku, usize)) -> [Box; 9] {
+        let mut sudoku = BLANK_SUDOKU;
+        sudoku.set_house(ROW_HOUSE, boxes);
+        solver(&mut sudoku, ROW_HOUSE);
+        sudoku.get_house(ROW_HOUSE)
+    }
 
-    // Check that we can run a normalisation over 9 boxes that may not all
-    // come from the same sudoku cell.
+    // Check that we can run a normalisation over the 9 boxes of a row.
     #[test]
     fn test_last_value_line() {
-        let mut line = Vec::new();
-
-        let mut box1 = BLANK_BOX;
-        let mut box2 = Box::from_val(2);
-        let mut box3 = Box::from_val(3);
-        let mut box4 = Box::from_val(4);
-        let mut box5 = Box::from_val(5);
-        let mut box6 = Box::from_val(6);
-        let mut box7 = Box::from_val(7);
-        let mut box8 = Box::from_val(8);
-        let mut box9 = Box::from_val(9);
-
-        line.push(&mut box1);
-        line.push(&mut box2);
-        line.push(&mut box3);
-        line.push(&mut box4);
-        line.push(&mut box5);
-        line.push(&mut box6);
-        line.push(&mut box7);
-        line.push(&mut box8);
-        line.push(&mut box9);
-
-        // When we normalise a line from a random selection of boxes that are
-        // interconnected we pass in reference to the 9 elements and they are modified
-        // in place.
-        single_position_array(line);
-
-        assert!(box1.value == Some(1));
+        let line = run_on_row(
+            [
+                BLANK_BOX,
+                Box::from_val(2),
+                Box::from_val(3),
+                Box::from_val(4),
+                Box::from_val(5),
+                Box::from_val(6),
+                Box::from_val(7),
+                Box::from_val(8),
+                Box::from_val(9),
+            ],
+            single_position_house,
+        );
+
+        assert!(line[0].value == Some(1));
     }
 
     #[test]
     fn test_remove_possibles() {
-        let mut line = Vec::new();
-
-        let mut box1 = BLANK_BOX;
-        let mut box2 = Box::from_val(2);
-        let mut box3 = Box::from_val(3);
-        let mut box4 = Box::from_val(4);
-        let mut box5 = BLANK_BOX;
-        let mut box6 = Box::from_val(6);
-        let mut box7 = Box::from_val(7);
-        let mut box8 = Box::from_val(8);
-        let mut box9 = BLANK_BOX;
-
-        line.push(&mut box1);
-        line.push(&mut box2);
-        line.push(&mut box3);
-        line.push(&mut box4);
-        line.push(&mut box5);
-        line.push(&mut box6);
-        line.push(&mut box7);
-        line.push(&mut box8);
-        line.push(&mut box9);
-
-        // When we normalise a line from a random selection of boxes that are
-        // interconnected we pass in reference to the 9 elements and they are modified
-        // in place.
-        single_position_array(line);
-
-        assert!(box1.get_possibles() == [1, 5, 9]);
-        assert!(box5.get_possibles() == [1, 5, 9]);
-        assert!(box9.get_possibles() == [1, 5, 9]);
+        let line = run_on_row(
+            [
+                BLANK_BOX,
+                Box::from_val(2),
+                Box::from_val(3),
+                Box::from_val(4),
+                BLANK_BOX,
+                Box::from_val(6),
+                Box::from_val(7),
+                Box::from_val(8),
+                BLANK_BOX,
+            ],
+            single_position_house,
+        );
+
+        assert!(line[0].get_possibles() == [1, 5, 9]);
+        assert!(line[4].get_possibles() == [1, 5, 9]);
+        assert!(line[8].get_possibles() == [1, 5, 9]);
     }
 
     #[test]
@@ -684,136 +660,100 @@ mod tests {
 
     #[test]
     fn test_box_simplification_factor_2() {
-        let mut line = Vec::new();
-
-        let mut box1 = Box::from_possibles([1, 2].to_vec());
-        let mut box2 = Box::from_possibles([1, 2].to_vec());
-        let mut box3 = Box::from_possibles([1, 2, 3].to_vec());
-        let mut box4 = Box::from_possibles([1, 2, 4].to_vec());
-        let mut box5 = Box::from_possibles([1, 2, 5].to_vec());
-        let mut box6 = Box::from_possibles([1, 2, 6].to_vec());
-        let mut box7 = Box::from_possibles([1, 2, 7].to_vec());
-        let mut box8 = Box::from_possibles([1, 2, 8].to_vec());
-        let mut box9 = Box::from_possibles([1, 2, 9].to_vec());
-
-        line.push(&mut box1);
-        line.push(&mut box2);
-        line.push(&mut box3);
-        line.push(&mut box4);
-        line.push(&mut box5);
-        line.push(&mut box6);
-        line.push(&mut box7);
-        line.push(&mut box8);
-        line.push(&mut box9);
-
-        naked_set_array(line);
-
-        assert_eq!(box3.get_possibles(), [3]);
-        assert_eq!(box4.get_possibles(), [4]);
-        assert_eq!(box5.get_possibles(), [5]);
-        assert_eq!(box6.get_possibles(), [6]);
-        assert_eq!(box7.get_possibles(), [7]);
-        assert_eq!(box8.get_possibles(), [8]);
-        assert_eq!(box9.get_possibles(), [9]);
+        let line = run_on_row(
+            [
+                Box::from_possibles([1, 2].to_vec()),
+                Box::from_possibles([1, 2].to_vec()),
+                Box::from_possibles([1, 2, 3].to_vec()),
+                Box::from_possibles([1, 2, 4].to_vec()),
+                Box::from_possibles([1, 2, 5].to_vec()),
+                Box::from_possibles([1, 2, 6].to_vec()),
+                Box::from_possibles([1, 2, 7].to_vec()),
+                Box::from_possibles([1, 2, 8].to_vec()),
+                Box::from_possibles([1, 2, 9].to_vec()),
+            ],
+            naked_set_house,
+        );
+
+        assert_eq!(line[2].get_possibles(), [3]);
+        assert_eq!(line[3].get_possibles(), [4]);
+        assert_eq!(line[4].get_possibles(), [5]);
+        assert_eq!(line[5].get_possibles(), [6]);
+        assert_eq!(line[6].get_possibles(), [7]);
+        assert_eq!(line[7].get_possibles(), [8]);
+        assert_eq!(line[8].get_possibles(), [9]);
     }
 
     #[test]
     fn test_box_simplification_factor_3() {
-        let mut line = Vec::new();
-
-        let mut box1 = Box::from_possibles([1, 2, 3].to_vec());
-        let mut box2 = Box::from_possibles([1, 2, 3].to_vec());
-        let mut box3 = Box::from_possibles([1, 2, 3].to_vec());
-        let mut box4 = Box::from_possibles([1, 2, 4].to_vec());
-        let mut box5 = Box::from_possibles([1, 2, 5].to_vec());
-        let mut box6 = Box::from_possibles([1, 2, 6].to_vec());
-        let mut box7 = Box::from_possibles([1, 2, 7].to_vec());
-        let mut box8 = Box::from_possibles([1, 2, 8].to_vec());
-        let mut box9 = Box::from_possibles([1, 2, 9].to_vec());
-
-        line.push(&mut box1);
-        line.push(&mut box2);
-        line.push(&mut box3);
-        line.push(&mut box4);
-        line.push(&mut box5);
-        line.push(&mut box6);
-        line.push(&mut box7);
-        line.push(&mut box8);
-        line.push(&mut box9);
-
-        naked_set_array(line);
-
-        assert_eq!(box4.get_possibles(), [4]);
-        assert_eq!(box5.get_possibles(), [5]);
-        assert_eq!(box6.get_possibles(), [6]);
-        assert_eq!(box7.get_possibles(), [7]);
-        assert_eq!(box8.get_possibles(), [8]);
-        assert_eq!(box9.get_possibles(), [9]);
+        let line = run_on_row(
+            [
+                Box::from_possibles([1, 2, 3].to_vec()),
+                Box::from_possibles([1, 2, 3].to_vec()),
+                Box::from_possibles([1, 2, 3].to_vec()),
+                Box::from_possibles([1, 2, 4].to_vec()),
+                Box::from_possibles([1, 2, 5].to_vec()),
+                Box::from_possibles([1, 2, 6].to_vec()),
+                Box::from_possibles([1, 2, 7].to_vec()),
+                Box::from_possibles([1, 2, 8].to_vec()),
+                Box::from_possibles([1, 2, 9].to_vec()),
+            ],
+            naked_set_house,
+        );
+
+        assert_eq!(line[3].get_possibles(), [4]);
+        assert_eq!(line[4].get_possibles(), [5]);
+        assert_eq!(line[5].get_possibles(), [6]);
+        assert_eq!(line[6].get_possibles(), [7]);
+        assert_eq!(line[7].get_possibles(), [8]);
+        assert_eq!(line[8].get_possibles(), [9]);
     }
 
     #[test]
     fn test_box_simplification_factor_4() {
-        let mut line = Vec::new();
-
-        let mut box1 = Box::from_possibles([1, 2, 3, 4].to_vec());
-        let mut box2 = Box::from_possibles([1, 2, 3, 4].to_vec());
-        let mut box3 = Box::from_possibles([1, 2, 3, 4].to_vec());
-        let mut box4 = Box::from_possibles([1, 2, 3, 4].to_vec());
-        let mut box5 = Box::from_possibles([1, 2, 3, 5].to_vec());
-        let mut box6 = Box::from_possibles([1, 3, 6].to_vec());
-        let mut box7 = Box::from_possibles([1, 2, 7].to_vec());
-        let mut box8 = Box::from_possibles([1, 3, 8].to_vec());
-        let mut box9 = Box::from_possibles([1, 2, 9].to_vec());
-
-        line.push(&mut box1);
-        line.push(&mut box2);
-        line.push(&mut box3);
-        line.push(&mut box4);
-        line.push(&mut box5);
-        line.push(&mut box6);
-        line.push(&mut box7);
-        line.push(&mut box8);
-        line.push(&mut box9);
-
-        naked_set_array(line);
-
-        assert_eq!(box5.get_possibles(), [5]);
-        assert_eq!(box6.get_possibles(), [6]);
-        assert_eq!(box7.get_possibles(), [7]);
-        assert_eq!(box8.get_possibles(), [8]);
-        assert_eq!(box9.get_possibles(), [9]);
+        let line = run_on_row(
+            [
+                Box::from_possibles([1, 2, 3, 4].to_vec()),
+                Box::from_possibles([1, 2, 3, 4].to_vec()),
+                Box::from_possibles([1, 2, 3, 4].to_vec()),
+                Box::from_possibles([1, 2, 3, 4].to_vec()),
+                Box::from_possibles([1, 2, 3, 5].to_vec()),
+                Box::from_possibles([1, 3, 6].to_vec()),
+                Box::from_possibles([1, 2, 7].to_vec()),
+                Box::from_possibles([1, 3, 8].to_vec()),
+                Box::from_possibles([1, 2, 9].to_vec()),
+            ],
+            naked_set_house,
+        );
+
+        assert_eq!(line[4].get_possibles(), [5]);
+        assert_eq!(line[5].get_possibles(), [6]);
+        assert_eq!(line[6].get_possibles(), [7]);
+        assert_eq!(line[7].get_possibles(), [8]);
+        assert_eq!(line[8].get_possibles(), [9]);
     }
 
     #[test]
     fn test_box_simplification_factor_5() {
-        let mut line = Vec::new();
-
-        let mut box1 = Box::from_possibles([1, 2, 3, 4, 5].to_vec());
-        let mut box2 = Box::from_possibles([1, 2, 3, 4, 5].to_vec());
-        let mut box3 = Box::from_possibles([1, 2, 3, 4, 5].to_vec());
-        let mut box4 = Box::from_possibles([1, 2, 3, 4, 5].to_vec());
-        let mut box5 = Box::from_possibles([1, 2, 3, 4, 5].to_vec());
-        let mut box6 = Box::from_possibles([1, 5, 6].to_vec());
-        let mut box7 = Box::from_possibles([1, 2, 7].to_vec());
-        let mut box8 = Box::from_possibles([5, 3, 8].to_vec());
-        let mut box9 = Box::from_possibles([4, 2, 9].to_vec());
-
-        line.push(&mut box1);
-        line.push(&mut box2);
-        line.push(&mut box3);
-        line.push(&mut box4);
-        line.push(&mut box5);
-        line.push(&mut box6);
-        line.push(&mut box7);
-        line.push(&mut box8);
-        line.push(&mut box9);
-
-        naked_set_array(line);
-
-        assert_eq!(box6.get_possibles(), [6]);
-        assert_eq!(box7.get_possibles(), [7]);
-        assert_eq!(box8.get_possibles(), [8]);
-        assert_eq!(box9.get_possibles(), [9]);
+        let line = run_on_row(
+            [
+                Box::from_possibles([1, 2, 3, 4, 5].to_vec()),
+                Box::from_possibles([1, 2, 3, 4, 5].to_vec()),
+                Box::from_possibles([1, 2, 3, 4, 5].to_vec()),
+                Box::from_possibles([1, 2, 3, 4, 5].to_vec()),
+                Box::from_possibles([1, 2, 3, 4, 5].to_vec()),
+                Box::from_possibles([1, 5, 6].to_vec()),
+                Box::from_possibles([1, 2, 7].to_vec()),
+                Box::from_possibles([5, 3, 8].to_vec()),
+                Box::from_possibles([4, 2, 9].to_vec()),
+            ],
+            naked_set_house,
+        );
+
+        assert_eq!(line[5].get_possibles(), [6]);
+        assert_eq!(line[6].get_possibles(), [7]);
+        assert_eq!(line[7].get_possibles(), [8]);
+        assert_eq!(line[8].get_possibles(), [9]);
     }
 
     #[test]
@@ -887,9 +827,7 @@ mod tests {
     fn test_naive_row_solve() {
         let mut sudoku = Sudoku::from_ss("test/easy_solve.ss".to_string()).unwrap();
 
-        // How does this not need a mut???
-        let row = sudoku.get_row_mut(0);
-        single_position_array(row);
+        single_position_house(&mut sudoku, ROW_HOUSE);
         assert_eq!(sudoku.cells[TOP_LFT].boxes[TOP_LFT], Box::from_val(1));
     }
 
@@ -1,8 +1,11 @@
+use crate::forcing;
+use crate::houses::*;
 use crate::sk_box::*;
 use crate::sk_cell::*;
 use crate::solvers;
-use std::fs;
-use std::io::BufRead;
+use std::fmt;
+use std::io;
+use std::io::{BufRead, IsTerminal};
 // use boxy::{Char, Weight};
 
 // Setup a data structure that represents a sudoku. It is made up on overall Sudouko, which
@@ -22,10 +25,72 @@ use std::io::BufRead;
 // bitwise operators.
 
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sudoku {
     pub cells: [Cell; 9],
 }
 
+/**
+ * Change
+ *
+ * What's different about one box between two sudokus, from Sudoku::diff. The box is
+ * a row major index from 0 to 80. Possible values are only compared while the box
+ * isn't solved afterwards, as placing a value takes out everything else anyway.
+ *
+ * placed  - The value it has now that it didn't have before.
+ * cleared - The value it had before that it doesn't have now.
+ * removed - Possible values taken out.
+ * added   - Possible values put back.
+ */
+#[derive(PartialEq, Clone)]
+pub struct Change {
+    pub idx: usize,
+    pub placed: Option<u8>,
+    pub cleared: Option<u8>,
+    pub removed: Vec<u8>,
+    pub added: Vec<u8>,
+}
+
+// Written out like "r2c4: placed 5" or "r1c1: removed 3 8, added 2" so a failed
+// assert_eq! on a diff says what's wrong without a pair of whole grids to compare.
+impl fmt::Display for Change {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let values = |values: &[u8]| {
+            values
+                .iter()
+                .map(u8::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let mut parts = Vec::new();
+        if let Some(value) = self.cleared {
+            parts.push(format!("cleared {}", value));
+        }
+        if let Some(value) = self.placed {
+            parts.push(format!("placed {}", value));
+        }
+        if !self.removed.is_empty() {
+            parts.push(format!("removed {}", values(&self.removed)));
+        }
+        if !self.added.is_empty() {
+            parts.push(format!("added {}", values(&self.added)));
+        }
+        write!(
+            formatter,
+            "r{}c{}: {}",
+            self.idx / 9 + 1,
+            self.idx % 9 + 1,
+            parts.join(", ")
+        )
+    }
+}
+
+impl fmt::Debug for Change {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, formatter)
+    }
+}
+
 pub const BLANK_SUDOKU: Sudoku = Sudoku {
     cells: [
         BLANK_CELL, BLANK_CELL, BLANK_CELL, BLANK_CELL, BLANK_CELL, BLANK_CELL, BLANK_CELL,
@@ -33,188 +98,90 @@ pub const BLANK_SUDOKU: Sudoku = Sudoku {
     ],
 };
 
-pub enum FileType {
-    Simple,
-    Multi,
-    Possibles,
+/**
+ * Colour
+ *
+ * Whether to colour in changed boxes with terminal escape codes. Auto only does
+ * when the text is going to a terminal, so piping the output into a file keeps it
+ * clean. Only whoever's writing the text knows where it's going, so they say.
+ */
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Colour {
+    Never,
+    Always,
+    Auto,
 }
 
-impl Sudoku {
-    /**
-     * row_mut
-     *
-     * Returns a horizontal row across 3 cells. Takes an input 0-8 to which row to
-     * return.
-     *
-     * Returns a mutable vec of references to the cells so as to be updated by
-     * solving functions.
-     *
-     * This whole function is just fucked. It's way too complicated to get the
-     * results out using iter_mut and nth functions on them across the item
-     * and the weirdness in the function signature to set lifetimes on results
-     * I don't entirely understand. If something breaks when we make this program
-     * multi-threaded I'm pretty sure it will be here.
-     */
-    pub fn get_row_mut<'a>(&'a mut self, row: usize) -> Vec<&'a mut Box> {
-        let mut result = Vec::new();
-
-        // We will be iterating over 3 cells, and then 3 values
-        // from each cell. We use the input row to select the
-        // cell "offset" to get use to the right row of cells in the
-        // sudoku, and then a box offset to get us to the right row
-        // within each of those cells.
-        //
-        // Then we run a simple iteration over the cells, and then the boxes,
-        // adding the offsets each time, to add our values in.
+pub const COLOURS: [Colour; 3] = [Colour::Always, Colour::Never, Colour::Auto];
 
-        // Divide 3 * 3 to set to either 0, 3, or 6.
-        let cell_offset = (row / 3) * 3;
-
-        // To find the offset within the cell mod by 3, then * 3
-        // to be either 0, 3, or 6 within cell. Remember index from 0.
-        let box_offset = (row % 3) * 3;
-        let mut cell_iter = self.cells.iter_mut();
-
-        // Fast forward the cell iterator to the cell before the one we want
-        // to read. Only if the iterator isn't already there for 0.
-        if cell_offset > 0 {
-            cell_iter.nth(cell_offset - 1).unwrap();
+impl Colour {
+    pub fn name(self) -> &'static str {
+        match self {
+            Colour::Never => "never",
+            Colour::Always => "always",
+            Colour::Auto => "auto",
         }
+    }
 
-        for _x in 0..3 {
-            let cell = cell_iter.next().unwrap();
-
-            let mut box_iter = cell.boxes.iter_mut();
-
-            // Fast forward to the right row of the cell if necessary
-            if box_offset > 0 {
-                box_iter.nth(box_offset - 1).unwrap();
-            }
+    pub fn from_name(name: &str) -> Option<Colour> {
+        COLOURS.iter().copied().find(|colour| colour.name() == name)
+    }
 
-            for _y in 0..3 {
-                let read_box = box_iter.next().unwrap();
-                result.push(read_box);
-            }
+    pub fn enabled(self, terminal: bool) -> bool {
+        match self {
+            Colour::Never => false,
+            Colour::Always => true,
+            Colour::Auto => terminal,
         }
-
-        result
     }
+}
 
+impl Sudoku {
     /**
-     * get_col_mut
-     *
-     * Returns a vertical column across 3 cells. Takes an input 0-8 to which column to
-     * return.
+     * box_at / box_at_mut
      *
-     * Returns a mutable vec of references to the cells so as to be updated by
-     * solving functions.
-     *
-     * This whole function is just fucked. It's way too complicated to get the
-     * results out using iter_mut and nth functions on them across the item
-     * and the weirdness in the function signature to set lifetimes on results
-     * I don't entirely understand. If something breaks when we make this program
-     * multi-threaded I'm pretty sure it will be here.
+     * Get a box by its index from 0 to 80, counting across the rows from the top
+     * left. See houses.rs for tables of indexes to use with these.
      */
-    pub fn get_col_mut<'a>(&'a mut self, col: usize) -> Vec<&mut Box> {
-        assert!(col <= 8);
-
-        let mut result = Vec::new();
-
-        // We will be iterating over 3 cells, and then 3 values
-        // from each cell. We use the input row to select the
-        // cell "offset" to get use to the right column of cells
-        // (either 0, 1 or 2 offset) and then add 3 each time to
-        // have a pattern of 0, 3, 6 /  1, 4, 7 / 2, 5, 8.
-        //
-        // Then the same patter to get the boxes within in column.
-        //
-        // Then we run a simple iteration over the cells, and then the boxes,
-        // adding 3 each time.
-
-        let cell_offset = col / 3;
-
-        // To find the offset within the cell just mod by 3.
-        let box_offset = col % 3;
+    pub fn box_at(&self, idx: usize) -> &Box {
+        let (cell, box_idx) = CELL_BOX[idx];
+        &self.cells[cell].boxes[box_idx]
+    }
 
-        let mut cell_iter = self.cells.iter_mut();
+    pub fn box_at_mut(&mut self, idx: usize) -> &mut Box {
+        let (cell, box_idx) = CELL_BOX[idx];
+        &mut self.cells[cell].boxes[box_idx]
+    }
 
-        // Fast forward the cell iterator so the next cell it returns is the one
-        // we want (0, 1, or 2).
-        //
-        // It would be "neater" to use get_nth to get the 3rd box every time to
-        // scan 'vertically' down the array but it ends up messier code to deal
-        // with getting the first box differently each time.
-        if cell_offset > 0 {
-            cell_iter.nth(cell_offset - 1).unwrap();
+    /**
+     * get_house
+     *
+     * Copy out the 9 boxes of a house (rows 0-8, columns 9-17, cells 18-26) so they
+     * can be worked on without holding any references into the sudoku. Put them
+     * back with set_house.
+     */
+    pub fn get_house(&self, house: usize) -> [Box; 9] {
+        let mut result = [BLANK_BOX; 9];
+        for (i, &idx) in HOUSES[house].iter().enumerate() {
+            result[i] = *self.box_at(idx);
         }
+        result
+    }
 
-        for x in 0..3 {
-            let cell = cell_iter.next().unwrap();
-
-            let mut box_iter = cell.boxes.iter_mut();
-
-            // Again we have to "fast forward, but now within the cell to the
-            // right box.
-            if box_offset > 0 {
-                box_iter.nth(box_offset - 1).unwrap();
-            }
-
-            for y in 0..3 {
-                let read_box = box_iter.next().unwrap();
-                result.push(read_box);
-
-                // Now we skip the next two boxes unless we were reading the last value.
-                if y < 2 {
-                    box_iter.next().unwrap();
-                    box_iter.next().unwrap();
-                }
-            }
-
-            // Now we skip past the next two cells so that the start of the next loop will
-            // read the next cell "down" on the array unless we're on the last read.
-            if x < 2 {
-                cell_iter.next().unwrap();
-                cell_iter.next().unwrap();
-            }
+    pub fn set_house(&mut self, house: usize, boxes: [Box; 9]) {
+        for (i, &idx) in HOUSES[house].iter().enumerate() {
+            *self.box_at_mut(idx) = boxes[i];
         }
-
-        result
     }
 
     pub fn get_row(&self, row: usize) -> [Box; 9] {
         assert!(row < 9);
-        let cell_offset = (row / 3) * 3;
-        let box_offset = (row % 3) * 3;
-
-        [
-            self.lookup(cell_offset, box_offset),
-            self.lookup(cell_offset, box_offset + 1),
-            self.lookup(cell_offset, box_offset + 2),
-            self.lookup(cell_offset + 1, box_offset),
-            self.lookup(cell_offset + 1, box_offset + 1),
-            self.lookup(cell_offset + 1, box_offset + 2),
-            self.lookup(cell_offset + 2, box_offset),
-            self.lookup(cell_offset + 2, box_offset + 1),
-            self.lookup(cell_offset + 2, box_offset + 2),
-        ]
+        self.get_house(ROW_HOUSE + row)
     }
 
     pub fn get_col(&self, col: usize) -> [Box; 9] {
         assert!(col < 9);
-        let cell_offset = col / 3;
-        let box_offset = col % 3;
-
-        [
-            self.lookup(cell_offset, box_offset),
-            self.lookup(cell_offset, box_offset + 3),
-            self.lookup(cell_offset, box_offset + 6),
-            self.lookup(cell_offset + 3, box_offset),
-            self.lookup(cell_offset + 3, box_offset + 3),
-            self.lookup(cell_offset + 3, box_offset + 6),
-            self.lookup(cell_offset + 6, box_offset),
-            self.lookup(cell_offset + 6, box_offset + 3),
-            self.lookup(cell_offset + 6, box_offset + 6),
-        ]
+        self.get_house(COL_HOUSE + col)
     }
 
     /**
@@ -227,6 +194,7 @@ impl Sudoku {
      *
      * Note - doesn't return a ref, but a copy so cannot be used to modify sudoku!
      */
+    #[cfg(test)]
     pub fn lookup(&self, cell_idx: usize, box_idx: usize) -> Box {
         self.cells[cell_idx].boxes[box_idx]
     }
@@ -235,7 +203,23 @@ impl Sudoku {
     // yet to have it return a more generic error so just using io::Error
     //
     // File Format taken from Simple Sudoku
+    #[cfg(test)]
     pub fn from_ss(filename: String) -> Result<Sudoku, &'static str> {
+        let text = match std::fs::read_to_string(filename) {
+            Ok(text) => text,
+            Err(error) => panic!("Problem opening the file: {:?}", error),
+        };
+
+        Self::parse_ss(&text)
+    }
+
+    /**
+     * parse_ss
+     *
+     * The same as from_ss but for text already read in, so it can come from
+     * anywhere.
+     */
+    pub fn parse_ss(text: &str) -> Result<Sudoku, &'static str> {
         // We expect to read a stream of numbers set out in the same
         // way a sudo would be printed on page, with "|" and "-" marks
         // used to break up the cells and the boxes in each cell just seperated by
@@ -254,14 +238,7 @@ impl Sudoku {
         // |345|345|345|
         // |678|678|678|
 
-        // Attempt to open the file
-        let file = fs::File::open(filename);
-        let file = match file {
-            Ok(file) => file,
-            Err(error) => panic!("Problem opening the file: {:?}", error),
-        };
-
-        let mut reader = std::io::BufReader::new(file);
+        let mut reader = text.as_bytes();
 
         // Instantiatie sudoku as blank
         let mut sudoku = BLANK_SUDOKU;
@@ -283,17 +260,18 @@ impl Sudoku {
         for cur_cel_row in 0..3 {
             for cur_box_row in 0..3 {
                 // Read a new line that crosses across all of the boxes.
-                let length = reader.read_line(&mut line).expect("Could not read line");
+                let length = reader
+                    .read_line(&mut line)
+                    .map_err(|_| "could not read line")?;
 
                 // Make sure there's enough data in line for all the row. May be 14 or 15 lines
                 // depending on whether it's a unix or windows style text file.
-                if length == 15 {
-                    assert_eq!(line.pop(), Some('\n'));
-                    assert_eq!(line.pop(), Some('\r'));
-                } else if length == 14 {
-                    assert_eq!(line.pop(), Some('\n'));
+                let unix = length == 14 && line.ends_with('\n');
+                let windows = length == 15 && line.ends_with("\r\n");
+                if unix || windows {
+                    line.truncate(13);
                 } else {
-                    assert!(false);
+                    return Err("expected rows like |...|...|...|");
                 }
 
                 // Read charachters off from the RIGHT of the string using the pop
@@ -303,9 +281,11 @@ impl Sudoku {
                 // From 3 to 0 because we're going from right to left popping off end of the string.
                 for cur_cel_col in (0..3).rev() {
                     // Read off the first '|'
-                    assert_eq!(line.pop(), Some('|'));
+                    if line.pop() != Some('|') {
+                        return Err("expected rows like |...|...|...|");
+                    }
                     for cur_box_col in (0..3).rev() {
-                        let char = line.pop().expect("Expected box value, got EoL");
+                        let char = line.pop().ok_or("expected rows like |...|...|...|")?;
 
                         // Find the index of the cel and box to write into by multipleying
                         // row by 3. This matches our treatment of a linear 9 element array
@@ -316,17 +296,23 @@ impl Sudoku {
                         // To convert row and col to an index just times
                         // the row by 3. This matches our structure of a 9 element
                         // linear array represeting a 3x3 array
-                        sudoku.cells[cell_idx].boxes[box_idx] = Self::char_to_box(char);
+                        sudoku.cells[cell_idx].boxes[box_idx] = Self::char_to_box(char)?;
                     }
                 }
                 line.clear();
             }
             // Check for a row of plain "---------" and read to the next line.
             // But if there's no lines left that's OK if we just read cell row 3
-            reader.read_line(&mut line).expect("Could not read line.");
+            reader
+                .read_line(&mut line)
+                .map_err(|_| "could not read line")?;
             line.clear();
         }
 
+        if sudoku.repeated_value() {
+            return Err("value repeated");
+        }
+
         // Make sure that the "possibles" in each cell don't cross over with the
         // filled out values already in the cell.
         solvers::normalise(&mut sudoku);
@@ -334,7 +320,7 @@ impl Sudoku {
         // Make sure the sudoku is well formed
         sudoku.check();
 
-        return Ok(sudoku);
+        Ok(sudoku)
     }
 
     /**
@@ -344,68 +330,255 @@ impl Sudoku {
      * Read a sudoku from a simple line definition, often found in files tha
      * contain lots of sudokus, one of each line.
      *
-     * Just 81 numbers in a row for each value.
+     * Just 81 numbers in a row for each value. See parse_line for everything else
+     * that's allowed.
+     */
+    #[cfg(test)]
+    pub fn from_line(input: &str) -> Sudoku {
+        Self::parse_line(input).expect("Bad sudoku line")
+    }
+
+    /**
+     * parse_line
+     *
+     * from_line without the panics, for reading files where one bad line shouldn't
+     * take everything else down with it. Also catches givens that clash with each
+     * other as they'd only blow up later in the solver.
+     *
+     * Blanks can be any of '.', '0', '-' or '_', and anything after the 81 boxes
+     * that starts with a space or '#' is ignored. Use parse_line_metadata to get
+     * hold of it instead.
+     */
+    pub fn parse_line(input: &str) -> Result<Sudoku, &'static str> {
+        Self::parse_line_metadata(input).map(|(sudoku, _)| sudoku)
+    }
+
+    /**
+     * parse_line_metadata
+     *
+     * parse_line, but also giving back whatever came after the boxes - often a
+     * rating or a name - with the space or '#' in front of it taken off.
      */
-    pub fn from_line(input: &String) -> Sudoku {
+    pub fn parse_line_metadata(input: &str) -> Result<(Sudoku, Option<String>), &'static str> {
+        let input = input.trim();
+        let end = input
+            .find(|c: char| c.is_whitespace() || c == '#')
+            .unwrap_or(input.len());
+        let (input, rest) = input.split_at(end);
+
+        let rest = rest.trim_start();
+        let rest = rest.strip_prefix('#').unwrap_or(rest).trim();
+        let metadata = if rest.is_empty() {
+            None
+        } else {
+            Some(rest.to_string())
+        };
+
         let mut result: Sudoku = BLANK_SUDOKU;
-        // I feel like a bad person for indexing from 1.
-        let mut row = 1;
-        let mut col = 1;
-
-        assert!(input.len() == 81);
-
-        for c in input.chars() {
-            result.box_set(row, col, Self::char_to_box(c));
-            row += 1;
-            if row == 10 {
-                row = 1;
-                col += 1;
-            }
+
+        if input.chars().count() != 81 {
+            return Err("expected 81 boxes");
+        }
+
+        for (idx, c) in input.chars().enumerate() {
+            let sk_box = match c {
+                '.' | '0' | '-' | '_' => BLANK_BOX,
+                '1'..='9' => Box::from_val(c as u8 - b'0'),
+                _ => return Err("expected a number between 1 and 9 or a blank"),
+            };
+            *result.box_at_mut(idx) = sk_box;
+        }
+
+        if result.repeated_value() {
+            return Err("value repeated");
         }
-        assert!(row == 1);
-        assert!(col == 10);
 
         solvers::normalise(&mut result);
-        result
+        Ok((result, metadata))
     }
 
     /**
-     * Read every sudoku in a file and return them in a big array.
+     * repeated_value
+     *
+     * Whether any value turns up twice in a row, column or cell. Unlike check it
+     * doesn't panic, so it's for checking sudokus that have just been read in.
      */
-    pub fn from_txt(filename: String) -> Vec<Sudoku> {
+    pub fn repeated_value(&self) -> bool {
+        !self.conflicts().is_empty()
+    }
+
+    /**
+     * conflicts
+     *
+     * Every box holding a value that's also in another box in the same row, column
+     * or cell, as indexes from 0 to 80. These are what check would panic on, so this
+     * is for showing someone where they've gone wrong.
+     */
+    pub fn conflicts(&self) -> Vec<usize> {
         let mut result = Vec::new();
+        for (idx, peers) in PEERS.iter().enumerate() {
+            if let Some(value) = self.box_at(idx).value {
+                let repeated = peers
+                    .iter()
+                    .any(|&peer| self.box_at(peer).value == Some(value));
+                if repeated {
+                    result.push(idx);
+                }
+            }
+        }
+        result
+    }
 
-        let file = fs::File::open(filename);
-        let file = match file {
-            Ok(file) => file,
-            Err(error) => panic!("Problem opening the file: {:?}", error),
-        };
+    /**
+     * auto_marks
+     *
+     * The sudoku with the possible values of every unsolved box set to just the
+     * values that none of the boxes it can see hold. Unlike normalise it never fills
+     * anything in, even if there's only one value left.
+     */
+    pub fn auto_marks(&self) -> Sudoku {
+        let mut result = *self;
+        for (idx, peers) in PEERS.iter().enumerate() {
+            if self.box_at(idx).value.is_none() {
+                let sk_box = result.box_at_mut(idx);
+                *sk_box = BLANK_BOX;
+                for &peer in peers.iter() {
+                    if let Some(value) = self.box_at(peer).value {
+                        sk_box.remove_possible_value(value as u16);
+                    }
+                }
+            }
+        }
+        result
+    }
 
-        let mut reader = std::io::BufReader::new(file);
-        let mut line = String::new();
+    /**
+     * diff
+     *
+     * Every box that's different in other, in order, saying what got placed or
+     * cleared and which possible values went or came back. Empty if they're the same.
+     */
+    pub fn diff(&self, other: &Sudoku) -> Vec<Change> {
+        let mut result = Vec::new();
+        for idx in 0..81 {
+            let (old, new) = (self.box_at(idx), other.box_at(idx));
+            if old == new {
+                continue;
+            }
+            let mut change = Change {
+                idx,
+                placed: new.value.filter(|_| old.value != new.value),
+                cleared: old.value.filter(|_| old.value != new.value),
+                removed: Vec::new(),
+                added: Vec::new(),
+            };
+            if new.value.is_none() {
+                for value in 1..=9 {
+                    match (old.is_poss(value), new.is_poss(value)) {
+                        (true, false) => change.removed.push(value),
+                        (false, true) => change.added.push(value),
+                        _ => {}
+                    }
+                }
+            }
+            result.push(change);
+        }
+        result
+    }
 
-        while reader.read_line(&mut line).is_ok() {
-            // Skip any lines less than 81 long.
-            if line.len() == 84 {
-                assert_eq!(line.pop(), Some('\n'));
-                assert_eq!(line.pop(), Some('\r'));
-            } else if line.len() == 82 {
-                assert_eq!(line.pop(), Some('\n'));
-            } else if line.len() == 0 {
-                // Reading 0 length data shows we've reacehd the end of the file
-                break;
-            } else {
-                // Any length by 81 is an error - nothing else allowed int eh file but sudokus.
-                assert!(false);
+    /**
+     * values
+     *
+     * Just the solved values, in row-major order with 0 for a blank.
+     */
+    pub fn values(&self) -> [u8; 81] {
+        let mut result = [0; 81];
+        for (idx, value) in result.iter_mut().enumerate() {
+            *value = self.box_at(idx).value.unwrap_or(0);
+        }
+        result
+    }
+
+    /**
+     * to_line_string
+     *
+     * The reverse of from_line, all 81 boxes on one line with a '.' for anything
+     * not solved yet.
+     */
+    pub fn to_line_string(self) -> String {
+        let mut result = String::with_capacity(81);
+        for row in 1..=9 {
+            for col in 1..=9 {
+                result.push(self.get_c(col, row));
             }
+        }
+        result
+    }
 
-            result.push(Self::from_line(&line));
-            line.clear();
+    /**
+     * to_ss_string
+     *
+     * The reverse of from_ss, the values laid out as they would be on the page
+     * with '|' between the cells and a line of '-' between each band of them.
+     * Possible values are lost, so print_possibles is better for half solved
+     * sudokus.
+     */
+    pub fn to_ss_string(self) -> String {
+        let mut result = String::new();
+        for row in 1..=9 {
+            result.push('|');
+            for col in 1..=9 {
+                result.push(self.get_c(col, row));
+                if col % 3 == 0 {
+                    result.push('|');
+                }
+            }
+            result.push('\n');
+
+            // from_ss reads a line after every band, so the last one just gets an
+            // empty line.
+            if row == 3 || row == 6 {
+                result.push_str("-------------\n");
+            } else if row == 9 {
+                result.push('\n');
+            }
         }
+        result
+    }
 
+    /**
+     * to_possibles_string
+     *
+     * The reverse of from_possibles, the same big grid print_possibles shows
+     * with every possible value, but without any colours or commentary.
+     */
+    pub fn to_possibles_string(self) -> String {
+        let mut result = String::new();
+        self.write_possibles(&mut result, None, None, false)
+            .expect("Writing to a String can't fail");
         result
     }
 
+    /**
+     * Read every sudoku in a file and return them in a big array.
+     */
+    #[cfg(test)]
+    pub fn from_txt(filename: String) -> Vec<Sudoku> {
+        let file = std::fs::File::open(filename);
+        let file = match file {
+            Ok(file) => file,
+            Err(error) => panic!("Problem opening the file: {:?}", error),
+        };
+
+        // Nothing else allowed in the file but sudokus and comments.
+        crate::reader::SudokuReader::new(std::io::BufReader::new(file))
+            .map(|sudoku| match sudoku {
+                Ok(sudoku) => sudoku,
+                Err(error) => panic!("{}", error),
+            })
+            .collect()
+    }
+
     /**
          *
          * from_possibles
@@ -453,16 +626,26 @@ impl Sudoku {
     ╚═══════════╩═══════════╩═══════════╝
     w
          */
+    #[cfg(test)]
     pub fn from_possibles(filename: String) -> Sudoku {
-        let mut result = BLANK_SUDOKU;
-
-        let file = fs::File::open(filename);
-        let file = match file {
-            Ok(file) => file,
+        let text = match std::fs::read_to_string(filename) {
+            Ok(text) => text,
             Err(error) => panic!("Problem opening the file: {:?}", error),
         };
 
-        let mut reader = std::io::BufReader::new(file);
+        Self::parse_possibles(&text)
+            .unwrap_or_else(|reason| panic!("Bad possibles file: {}", reason))
+    }
+
+    /**
+     * parse_possibles
+     *
+     * The same as from_possibles but for text already read in, and saying what's
+     * wrong with it rather than panicking.
+     */
+    pub fn parse_possibles(text: &str) -> Result<Sudoku, &'static str> {
+        let mut result = BLANK_SUDOKU;
+        let mut reader = text.as_bytes();
         let mut line = String::new();
 
         // Track which line of the file we are up to.
@@ -476,22 +659,26 @@ impl Sudoku {
 
         // Now we loop over each line in the whole file
         while reader.read_line(&mut line).is_ok() {
-            if file_row % 4 == 0 {
-                // Every 4th line is purely decorative so no need to read, just ensure it's the
-                // right length and move on. Note we use chars().count() as the non-ascii chars
-                // we use to seperate numbers turn up as multiple bytes in unicode strings and
-                // thus mess up the count.
-                assert_eq!(line.chars().count(), 38);
-                file_row = file_row + 1;
-                line.clear();
-                continue;
-            }
-
             // Reach the end of the sudoku to read
             if file_row >= 37 {
                 break;
             }
 
+            // Every line should be the same width. Note we use chars().count() as the
+            // non-ascii chars we use to seperate numbers turn up as multiple bytes in
+            // unicode strings and thus mess up the count.
+            let width = line.trim_end_matches(['\n', '\r']).chars().count();
+            if width != 37 {
+                return Err("expected a possibles grid 37 characters wide and 37 lines long");
+            }
+
+            if file_row % 4 == 0 {
+                // Every 4th line is purely decorative so no need to read, just move on.
+                file_row += 1;
+                line.clear();
+                continue;
+            }
+
             // Now we can work out what row of the overall sudoku and of the box withing
             // the sudoku we're on by some simple division and mod values. Divide by 4
             // to find row from 1 to 3 as each row has 3 darta chars plus the seperator
@@ -501,27 +688,29 @@ impl Sudoku {
 
             let mut file_col = 0;
             for char in line.chars() {
+                // If we have reached end of line break out of the loop
+                if char == '\n' || char == '\r' {
+                    break;
+                }
+
                 if file_col % 4 == 0 {
                     // Skip over the seperating bits
-                    assert!(char == '|' || char == '║');
-                    file_col = file_col + 1;
+                    if char != '|' && char != '║' {
+                        return Err("expected '|' or '║' between boxes");
+                    }
+                    file_col += 1;
                     continue;
                 }
 
-                // If we have reached end of line break out of the loop
-                if char == '\n' {
-                    break;
-                }
-
                 // Now work out what colum of the sudoku we're reading from by some simple mode and
                 // division.
                 let cur_col = (file_col / 4) + 1;
                 let cur_box_col = file_col % 4;
 
-                assert!(cur_col >= 1 && cur_col <= 9);
-                assert!(cur_row >= 1 && cur_row <= 9);
-                assert!(cur_box_col >= 1 && cur_box_col <= 3);
-                assert!(cur_box_row >= 1 && cur_box_row <= 3);
+                assert!((1..=9).contains(&cur_col));
+                assert!((1..=9).contains(&cur_row));
+                assert!((1..=3).contains(&cur_box_col));
+                assert!((1..=3).contains(&cur_box_row));
 
                 // OK! Now we know what charachter we just read, the box it's for from the cur_row
                 // and cur_col and which of the 8 possible values withing the box it is from the
@@ -545,7 +734,9 @@ impl Sudoku {
                     // Mark that this cell is solved, this is also helpful if
                     solved[cell_idx][box_idx] = true;
                 } else {
-                    assert!(char >= '1' && char <= '9');
+                    if !('1'..='9').contains(&char) {
+                        return Err("expected a number between 1 and 9, a '.' or a space");
+                    }
 
                     if solved[cell_idx][box_idx] && value_idx == 5 {
                         // We found a value and we're in a 'solved' cell as indicated by having
@@ -556,29 +747,33 @@ impl Sudoku {
                     } else {
                         // Otherwise we are just showing a possible value for the box so we just
                         // ensure it's a number in the right position.
-                        assert!(char.to_digit(10).unwrap() as u8 == value_idx);
+                        if char.to_digit(10).unwrap() as u8 != value_idx {
+                            return Err("expected each possible value in its own place");
+                        }
                     }
                 }
 
-                file_col = file_col + 1;
+                file_col += 1;
             }
 
             line.clear();
-            file_row = file_row + 1;
+            file_row += 1;
         }
-        result
+
+        if (0..81).any(|idx| result.box_at(idx).get_possibles_bits() == 0) {
+            return Err("box with no possible values");
+        }
+        if result.repeated_value() {
+            return Err("value repeated");
+        }
+        Ok(result)
     }
 
-    fn char_to_box(char: char) -> Box {
-        if char == '.' {
-            BLANK_BOX
-        } else {
-            let digit = char.to_digit(10).expect("Expected number or '.'");
-            assert!(
-                digit >= 1 && digit <= 9,
-                "Expected a number between 1 and 9"
-            );
-            Box::from_val(digit as u8)
+    fn char_to_box(char: char) -> Result<Box, &'static str> {
+        match char {
+            '.' => Ok(BLANK_BOX),
+            '1'..='9' => Ok(Box::from_val(char as u8 - b'0')),
+            _ => Err("expected a number between 1 and 9 or a blank"),
         }
     }
 
@@ -603,12 +798,6 @@ impl Sudoku {
         (cell, idx)
     }
 
-    pub fn box_set(&mut self, col: usize, row: usize, sk_box: Box) {
-        let (cell, idx) = Self::col_row_to_cell_idx(col, row);
-
-        self.cells[cell].boxes[idx] = sk_box;
-    }
-
     pub fn get_c(&self, col: usize, row: usize) -> char {
         self.get_box(col, row).get_c()
     }
@@ -619,35 +808,97 @@ impl Sudoku {
         self.cells[cell].boxes[idx]
     }
 
+    /**
+     * print_ss
+     *
+     * Print the values to stderr, where the working goes so it stays out of the way
+     * of anything written to stdout.
+     */
     pub fn print_ss(&self) {
-        println!("-----------");
+        self.write_ss_io(&mut io::stderr())
+            .expect("Couldn't write to stderr");
+    }
+
+    /**
+     * write_ss / write_ss_io
+     *
+     * What print_ss shows, written to anything instead.
+     */
+    pub fn write_ss<W: fmt::Write>(&self, out: &mut W) -> fmt::Result {
+        writeln!(out, "-----------")?;
         for cur_row in 1..=9 {
             for cur_col in 1..=9 {
-                print!("{}", self.get_c(cur_col, cur_row));
+                write!(out, "{}", self.get_c(cur_col, cur_row))?;
                 if (cur_col > 0) && (cur_col % 3 == 0) && (cur_col < 9) {
-                    print!("|");
+                    write!(out, "|")?;
                 } else if cur_col == 9 {
-                    println!("");
+                    writeln!(out)?;
                 }
             }
 
             if cur_row > 0 && cur_row % 3 == 0 {
-                println!("-----------");
+                writeln!(out, "-----------")?;
             }
         }
+        Ok(())
+    }
+
+    pub fn write_ss_io<W: io::Write>(&self, out: &mut W) -> io::Result<()> {
+        let mut text = String::new();
+        self.write_ss(&mut text)
+            .expect("Writing to a String can't fail");
+        out.write_all(text.as_bytes())
     }
 
     /**
      * print_possibles
      *
-     * Print to screen a nice version of the sudoku that shows actual
+     * Print to stderr a nice version of the sudoku that shows actual
      * values and potential ones too. Every box is a 3x3 cell of numbers
-     * showing potential and/or actual values so it's a pretty big box.
+     * showing potential and/or actual values so it's a pretty big box. Like
+     * print_ss it's stderr so the working doesn't get mixed up with the answers.
      *
-     * diff - If provided print cells that differ from this sudoku a
-     *        different color
+     * diff   - If provided print cells that differ from this sudoku a
+     *          different color, if colour says to with stderr as the terminal
      */
-    pub fn print_possibles(&self, diff: Option<Sudoku>, commentary: Option<String>) {
+    pub fn print_possibles(
+        &self,
+        diff: Option<Sudoku>,
+        commentary: Option<String>,
+        colour: Colour,
+    ) {
+        let colour = colour.enabled(io::stderr().is_terminal());
+        self.write_possibles_io(&mut io::stderr(), diff, commentary, colour)
+            .expect("Couldn't write to stderr");
+    }
+
+    /**
+     * write_possibles / write_possibles_io
+     *
+     * What print_possibles shows, written to anything instead. Boxes that differ
+     * from diff are only coloured in if colour is true, so the text can go in files
+     * without escape codes all through it. See Colour::enabled to work it out.
+     */
+    pub fn write_possibles_io<W: io::Write>(
+        &self,
+        out: &mut W,
+        diff: Option<Sudoku>,
+        commentary: Option<String>,
+        colour: bool,
+    ) -> io::Result<()> {
+        let mut text = String::new();
+        self.write_possibles(&mut text, diff, commentary, colour)
+            .expect("Writing to a String can't fail");
+        out.write_all(text.as_bytes())
+    }
+
+    pub fn write_possibles<W: fmt::Write>(
+        &self,
+        out: &mut W,
+        diff: Option<Sudoku>,
+        commentary: Option<String>,
+        colour: bool,
+    ) -> fmt::Result {
         // Each box is a 3x3 cell of text. If no confirmed values each
         // potential is showin in it's own position (1-9) and excluded
         // values are shown as a .
@@ -692,22 +943,22 @@ impl Sudoku {
         // going forward.
         // Write the top line
 
-        print!(
-            "╔═══════════╦═══════════╦═══════════╗ {}",
-            commentary.unwrap_or("".to_string())
-        );
-        println!("");
+        write!(out, "╔═══════════╦═══════════╦═══════════╗")?;
+        if let Some(commentary) = commentary {
+            write!(out, " {}", commentary)?;
+        }
+        writeln!(out)?;
         // For each row of boxes in the sudoku we start a loop
         for row in 1..=9 {
             // If it's a "special" row we print some in-between decorations
             if row == 4 || row == 7 {
-                println!("╠═══════════╬═══════════╬═══════════╣");
+                writeln!(out, "╠═══════════╬═══════════╬═══════════╣")?;
             }
 
             // Each row of the sudoku actually has 3 rows of text for printing out the sudoku box
             // in boxes of 3x3
             for val_row in 1..=3 {
-                print!("║");
+                write!(out, "║")?;
                 for cell_col in 1..=3 {
                     // Each of the 3 columns of cells in the sudoku
                     for box_col in 1..=3 {
@@ -723,35 +974,36 @@ impl Sudoku {
                             //
                             let orig_box = self.get_box(cell, row);
                             let changed = match diff {
-                                None => false,
-                                Some(compare) => orig_box != compare.get_box(cell, row),
+                                Some(compare) if colour => orig_box != compare.get_box(cell, row),
+                                _ => false,
                             };
 
                             if changed {
-                                print!("\x1b[93m");
+                                write!(out, "\x1b[93m")?;
                             }
-                            print!(
+                            write!(
+                                out,
                                 "{}",
                                 self.get_box(cell, row)
                                     .get_pretty_c((val_row - 1) * 3 + val_col)
-                            );
+                            )?;
                             if changed {
-                                print!("\x1b[0m")
+                                write!(out, "\x1b[0m")?;
                             }
                         }
                         if box_col != 3 {
-                            print!("|");
+                            write!(out, "|")?;
                         }
                     }
-                    print!("║");
+                    write!(out, "║")?;
                 }
-                println!("");
+                writeln!(out)?;
             }
             if row != 3 && row != 6 && row != 9 {
-                println!("║---+---+---║---+---+---║---+---+---║");
+                writeln!(out, "║---+---+---║---+---+---║---+---+---║")?;
             }
         }
-        println!("╚═══════════╩═══════════╩═══════════╝");
+        writeln!(out, "╚═══════════╩═══════════╩═══════════╝")
     }
 
     // Check if the whole sudoku is solved.
@@ -765,7 +1017,7 @@ impl Sudoku {
             }
         }
 
-        return true;
+        true
     }
 
     // Check if the sudoku overall is still tip-top and internally consistent
@@ -773,10 +1025,8 @@ impl Sudoku {
     // consistency tests
     pub fn check(&self) {
         // Check all the cells are coherent.
-        let mut _i: usize = 0;
         for cell in self.cells {
             cell.check();
-            _i += 1;
         }
 
         // Checks each row for coherency
@@ -792,8 +1042,16 @@ impl Sudoku {
         }
     }
 
-    pub fn solve(&mut self, verbose: bool) {
-        self.print_possibles(None, Some("Solving".to_string()));
+    /**
+     * solve
+     *
+     * Solve as far as the techniques go. verbose prints every step of the way to
+     * stderr, colouring in the boxes each changes as it says.
+     */
+    pub fn solve(&mut self, verbose: Option<Colour>) {
+        if let Some(colour) = verbose {
+            self.print_possibles(None, Some("Solving".to_string()), colour);
+        }
         self.check();
         let mut i = 0;
         while !self.solved() {
@@ -802,35 +1060,54 @@ impl Sudoku {
 
             // Try naive solving
             solvers::single_position(self);
-            if verbose {
-                self.print_possibles(Some(prev), Some("Applied Single Position".to_string()));
+            if let Some(colour) = verbose {
+                self.print_possibles(
+                    Some(prev),
+                    Some("Applied Single Position".to_string()),
+                    colour,
+                );
             }
             self.check();
 
             prev = *self;
             solvers::naked_set(self);
-            if verbose {
-                self.print_possibles(Some(prev), Some("Applied Naked Set".to_string()));
+            if let Some(colour) = verbose {
+                self.print_possibles(Some(prev), Some("Applied Naked Set".to_string()), colour);
             }
             self.check();
 
             prev = *self;
             solvers::candidate_line(self);
-            if verbose {
-                self.print_possibles(Some(prev), Some("Applied Candidate Line".to_string()));
+            if let Some(colour) = verbose {
+                self.print_possibles(
+                    Some(prev),
+                    Some("Applied Candidate Line".to_string()),
+                    colour,
+                );
             }
             self.check();
 
-            // If we made no progress at all over the whole last round - then we don't have the
-            // abiliyt to solve this sudoku.
+            // If we made no progress at all over the whole last round - then before giving
+            // up try the contradiction based techniques, which are slow but thorough.
             if orig == *self {
-                if verbose {
-                    println!("Could not solve sudoku.");
+                if let Some(step) = forcing::forcing_step(self) {
+                    prev = *self;
+                    step.apply(self);
+                    if let Some(colour) = verbose {
+                        self.print_possibles(Some(prev), Some(format!("Applied {}", step)), colour);
+                    }
+                    self.check();
+                    i += 1;
+                    continue;
+                }
+
+                if verbose.is_some() {
+                    eprintln!("Could not solve sudoku.");
                 }
                 return;
             } else {
-                if verbose {
-                    println!("Going for round {}", i);
+                if verbose.is_some() {
+                    eprintln!("Going for round {}", i);
                 }
                 i += 1;
             }
@@ -843,6 +1120,7 @@ mod tests {
     use super::*;
     use crate::constants::*;
     use crate::solvers;
+    use std::fs;
 
     #[test]
     fn test_blank_read() {
@@ -875,92 +1153,96 @@ mod tests {
     }
 
     #[test]
-    fn test_row_read_mut() {
+    fn test_row_set() {
         let mut sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
 
         {
-            let mut row1 = sudoku.get_row_mut(0);
+            let mut row1 = sudoku.get_row(0);
 
             assert!(!row1[0].solved());
             assert!(!row1[1].solved());
             assert!(!row1[2].solved());
-            assert_eq!(*row1[3], Box::from_val(2));
-            assert_eq!(*row1[4], Box::from_val(6));
+            assert_eq!(row1[3], Box::from_val(2));
+            assert_eq!(row1[4], Box::from_val(6));
             assert!(!row1[5].solved());
-            assert_eq!(*row1[6], Box::from_val(7));
+            assert_eq!(row1[6], Box::from_val(7));
             assert!(!row1[7].solved());
-            assert_eq!(*row1[8], Box::from_val(1));
+            assert_eq!(row1[8], Box::from_val(1));
 
             // Check updating works at end of test.
             // This is the top_right box of the top right cell
-            *row1[8] = Box::from_val(9);
+            row1[8] = Box::from_val(9);
+            sudoku.set_house(ROW_HOUSE, row1);
         }
 
         {
-            let mut row3 = sudoku.get_row_mut(3);
-            assert_eq!(*row3[0], Box::from_val(8));
-            assert_eq!(*row3[1], Box::from_val(2));
+            let mut row3 = sudoku.get_row(3);
+            assert_eq!(row3[0], Box::from_val(8));
+            assert_eq!(row3[1], Box::from_val(2));
             assert!(!row3[2].solved());
-            assert_eq!(*row3[3], Box::from_val(1));
+            assert_eq!(row3[3], Box::from_val(1));
             assert!(!row3[4].solved());
             assert!(!row3[5].solved());
             assert!(!row3[6].solved());
-            assert_eq!(*row3[7], Box::from_val(4));
+            assert_eq!(row3[7], Box::from_val(4));
             assert!(!row3[8].solved());
 
             // Checked later at end of test
             // This is the top_mid box of the middle cell
-            *row3[3] = Box::from_val(4);
+            row3[3] = Box::from_val(4);
+            sudoku.set_house(ROW_HOUSE + 3, row3);
         }
         {
-            let mut row7 = sudoku.get_row_mut(7);
+            let mut row7 = sudoku.get_row(7);
             assert!(!row7[0].solved());
-            assert_eq!(*row7[1], Box::from_val(4));
+            assert_eq!(row7[1], Box::from_val(4));
             assert!(!row7[2].solved());
             assert!(!row7[3].solved());
-            assert_eq!(*row7[4], Box::from_val(5));
+            assert_eq!(row7[4], Box::from_val(5));
             assert!(!row7[5].solved());
             assert!(!row7[6].solved());
-            assert_eq!(*row7[7], Box::from_val(3));
-            assert_eq!(*row7[8], Box::from_val(6));
+            assert_eq!(row7[7], Box::from_val(3));
+            assert_eq!(row7[8], Box::from_val(6));
 
             // Checked later at end of test
-            *row7[8] = Box::from_val(2);
+            row7[8] = Box::from_val(2);
+            sudoku.set_house(ROW_HOUSE + 7, row7);
         }
 
-        // Now check that we can update values using these calls.
+        // Now check the changes went back into the sudoku.
         assert_eq!(sudoku.lookup(TOP_RHT, TOP_RHT).value, Some(9));
         assert_eq!(sudoku.lookup(MID_MID, TOP_LFT).value, Some(4));
         assert_eq!(sudoku.lookup(BOT_RHT, MID_RHT).value, Some(2));
     }
 
     #[test]
-    fn test_col_read_mut() {
+    fn test_col_set() {
         let mut sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
 
         {
-            let mut col1 = sudoku.get_col_mut(0);
+            let mut col1 = sudoku.get_col(0);
             assert!(!col1[0].solved());
-            assert_eq!(*col1[1], Box::from_val(6));
-            assert_eq!(*col1[2], Box::from_val(1));
-            assert_eq!(*col1[3], Box::from_val(8));
+            assert_eq!(col1[1], Box::from_val(6));
+            assert_eq!(col1[2], Box::from_val(1));
+            assert_eq!(col1[3], Box::from_val(8));
             assert!(!col1[4].solved());
             assert!(!col1[5].solved());
             assert!(!col1[6].solved());
             assert!(!col1[7].solved());
-            assert_eq!(*col1[8], Box::from_val(7));
+            assert_eq!(col1[8], Box::from_val(7));
 
             // Set this to test it later.
             // THis is Bottom left box of the top left cell
-            *col1[2] = Box::from_val(9);
+            col1[2] = Box::from_val(9);
+            sudoku.set_house(COL_HOUSE, col1);
         }
 
         {
-            let mut col3 = sudoku.get_col_mut(3);
-            assert_eq!(*col3[0], Box::from_val(2));
-            assert_eq!(*col3[3], Box::from_val(1));
-            assert_eq!(*col3[4], Box::from_val(6));
-            assert_eq!(*col3[6], Box::from_val(3));
+            let mut col3 = sudoku.get_col(3);
+            assert_eq!(col3[0], Box::from_val(2));
+            assert_eq!(col3[3], Box::from_val(1));
+            assert_eq!(col3[4], Box::from_val(6));
+            assert_eq!(col3[6], Box::from_val(3));
 
             assert!(!col3[1].solved());
             assert!(!col3[2].solved());
@@ -970,16 +1252,17 @@ mod tests {
 
             // Checked later at end of test
             // This is the mid-left box of the centre cell.
-            *col3[4] = Box::from_val(3);
+            col3[4] = Box::from_val(3);
+            sudoku.set_house(COL_HOUSE + 3, col3);
         }
 
         {
-            let mut col7 = sudoku.get_col_mut(7);
-            assert_eq!(*col7[1], Box::from_val(9));
-            assert_eq!(*col7[3], Box::from_val(4));
-            assert_eq!(*col7[5], Box::from_val(2));
-            assert_eq!(*col7[6], Box::from_val(7));
-            assert_eq!(*col7[7], Box::from_val(3));
+            let mut col7 = sudoku.get_col(7);
+            assert_eq!(col7[1], Box::from_val(9));
+            assert_eq!(col7[3], Box::from_val(4));
+            assert_eq!(col7[5], Box::from_val(2));
+            assert_eq!(col7[6], Box::from_val(7));
+            assert_eq!(col7[7], Box::from_val(3));
             assert!(!col7[0].solved());
             assert!(!col7[2].solved());
             assert!(!col7[4].solved());
@@ -987,10 +1270,11 @@ mod tests {
 
             // Checked later at end of test
             // This is mid bottom call of the bottom right cell
-            *col7[8] = Box::from_val(2);
+            col7[8] = Box::from_val(2);
+            sudoku.set_house(COL_HOUSE + 7, col7);
         }
 
-        // Now check that we can update values using these calls.
+        // Now check the changes went back into the sudoku.
         assert_eq!(sudoku.lookup(TOP_LFT, BOT_LFT).value, Some(9));
         assert_eq!(sudoku.lookup(MID_MID, MID_LFT).value, Some(3));
         assert_eq!(sudoku.lookup(BOT_RHT, BOT_MID).value, Some(2));
@@ -1045,8 +1329,6 @@ mod tests {
         // sudoku.cells[0].boxes[0].remove_possible_value(5);
         // sudoku.cells[0].boxes[0].remove_possible_value(9);
         solvers::single_position(&mut sudoku);
-
-        assert!(true);
     }
 
     #[test]
@@ -1058,8 +1340,6 @@ mod tests {
         let unsolved = Sudoku::from_ss("test/easy_solve.ss".to_string()).unwrap();
         let mut solved = unsolved;
         solvers::single_position(&mut solved);
-
-        assert!(true);
     }
 
     #[test]
@@ -1071,6 +1351,53 @@ mod tests {
         assert!(sudoku.solved());
     }
 
+    #[test]
+    fn test_conflicts() {
+        let mut sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        assert_eq!(sudoku.conflicts(), Vec::<usize>::new());
+        assert!(!sudoku.repeated_value());
+
+        // A 6 in r1c1 clashes with the one in r1c5 along the row, and r2c1 in the
+        // same cell and column.
+        sudoku.box_at_mut(0).set_val(6);
+        assert_eq!(sudoku.conflicts(), vec![0, 4, 9]);
+        assert!(sudoku.repeated_value());
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut before = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        *before.box_at_mut(0) = Box::from_possibles(vec![3, 4, 5]);
+        *before.box_at_mut(1) = Box::from_possibles(vec![2, 3]);
+        assert_eq!(before.diff(&before), vec![]);
+
+        let mut after = before;
+        after.box_at_mut(0).remove_possible_value(3);
+        after.box_at_mut(0).remove_possible_value(4);
+        after.box_at_mut(1).set_val(3);
+        after.box_at_mut(3).set_val(9);
+        let changes = before.diff(&after);
+        assert_eq!(
+            changes.iter().map(Change::to_string).collect::<Vec<_>>(),
+            vec![
+                "r1c1: removed 3 4",
+                "r1c2: placed 3",
+                "r1c4: cleared 2, placed 9"
+            ]
+        );
+        assert_eq!(changes[0].removed, vec![3, 4]);
+
+        // The other way round puts things back, apart from r1c2 which now has a
+        // possible value it didn't before.
+        let mut undo = after;
+        *undo.box_at_mut(1) = Box::from_possibles(vec![1, 2, 3]);
+        let changes = undo.diff(&before);
+        assert_eq!(changes[0].added, vec![3, 4]);
+        assert_eq!(changes[1].cleared, None);
+        assert_eq!(changes[1].removed, vec![1]);
+        assert_eq!(format!("{:?}", changes[2]), "r1c4: cleared 9, placed 2");
+    }
+
     #[test]
     fn test_from_line() {
         let sud_line = "\
@@ -1084,10 +1411,11 @@ mod tests {
             .......81\
             ...6.....";
 
-        let sudoku = Sudoku::from_line(&sud_line.to_string());
+        let sudoku = Sudoku::from_line(sud_line);
         assert_eq!(sudoku.get_c(1, 1), '6');
         assert_eq!(sudoku.get_c(5, 1), '5');
         assert_eq!(sudoku.get_c(9, 9), '.');
+        assert_eq!(sudoku.to_line_string(), sud_line);
     }
 
     #[test]
@@ -1126,7 +1454,7 @@ mod tests {
         assert_eq!(result.len(), 95);
 
         for mut sudoku in result {
-            sudoku.solve(true);
+            sudoku.solve(Some(Colour::Never));
         }
     }
 
@@ -1137,7 +1465,7 @@ mod tests {
         let mut j = 1;
 
         for mut sudoku in result {
-            sudoku.solve(true);
+            sudoku.solve(Some(Colour::Never));
             if sudoku.solved() {
                 i += 1;
             } else {
@@ -1146,14 +1474,181 @@ mod tests {
             println!("Solved {} of {} Sudokus!", i, i + j);
         }
 
-        // Current logic can solve 26 of the tests
-        assert_eq!(i, 26);
+        // With the forcing techniques as a last resort every one of the tests can be solved.
+        assert_eq!(i, 96);
     }
 
     #[test]
     fn test_read_possibles() {
         let result = Sudoku::from_possibles("test/possibles.txt".to_string());
 
-        result.print_possibles(None, None);
+        result.print_possibles(None, None, Colour::Auto);
+    }
+
+    // Every sudoku in test/ should come back out as text that reads back in the same.
+    #[test]
+    fn test_round_trip() {
+        let mut files = Vec::new();
+        for entry in fs::read_dir("test").unwrap() {
+            let path = entry.unwrap().path();
+            let text = fs::read_to_string(&path).unwrap();
+            let name = path.to_str().unwrap().to_string();
+
+            if name.ends_with(".mask") {
+                // Masks are shapes for the generator, not sudokus.
+                continue;
+            } else if text.starts_with('╔') {
+                // Possibles grids, including xwing_ready.ss.
+                let sudoku = Sudoku::parse_possibles(&text).unwrap();
+                let written = sudoku.to_possibles_string();
+                assert_eq!(written.trim_end(), text.trim_end(), "{}", name);
+                assert_eq!(
+                    Sudoku::parse_possibles(&written).unwrap(),
+                    sudoku,
+                    "{}",
+                    name
+                );
+            } else if text.starts_with('|') {
+                // Reading normalises, which can fill in blanks, so the text only
+                // matches once it's been through once. The givens still come out
+                // where they were, though the lines between rows may not.
+                let sudoku = Sudoku::parse_ss(&text).unwrap();
+                let written = sudoku.to_ss_string();
+                let rows = |text: &str| -> Vec<String> {
+                    text.lines()
+                        .filter(|line| line.chars().any(|c| c == '.' || c.is_ascii_digit()))
+                        .map(|line| line.to_string())
+                        .collect()
+                };
+                let (before, after) = (rows(&text), rows(&written));
+                assert_eq!(before.len(), 9, "{}", name);
+                assert_eq!(after.len(), 9, "{}", name);
+                for (before, after) in before.iter().zip(after.iter()) {
+                    assert_eq!(before.len(), after.len(), "{}", name);
+                    for (b, a) in before.chars().zip(after.chars()) {
+                        assert!(b == a || b == '.', "{}: {} became {}", name, b, a);
+                    }
+                }
+                assert_eq!(Sudoku::parse_ss(&written).unwrap(), sudoku, "{}", name);
+                assert_eq!(Sudoku::parse_ss(&written).unwrap().to_ss_string(), written);
+            } else if name.ends_with(".txt") {
+                for sudoku in Sudoku::from_txt(name.clone()) {
+                    let written = sudoku.to_line_string();
+                    assert_eq!(Sudoku::parse_line(&written).unwrap(), sudoku, "{}", name);
+                }
+            } else if name.ends_with(".cand") {
+                for sudoku in Sudoku::from_candidates(name.clone()).unwrap() {
+                    let written = sudoku.to_candidates_string();
+                    assert_eq!(Sudoku::parse_candidates(&written).unwrap(), sudoku);
+                }
+            } else if name.ends_with(".sdk") {
+                let puzzle = Sudoku::from_sdk(name.clone()).unwrap();
+                let written = puzzle.to_sdk_string();
+                assert_eq!(crate::sadman::SdkPuzzle::parse(&written).unwrap(), puzzle);
+            } else if name.ends_with(".sdm") {
+                let sudokus = Sudoku::from_sdm(name.clone()).unwrap();
+                let written = crate::sadman::to_sdm_string(&sudokus);
+                let reread: Vec<Sudoku> = written
+                    .lines()
+                    .map(|line| Sudoku::parse_line(line).unwrap())
+                    .collect();
+                assert_eq!(reread, sudokus, "{}", name);
+            } else {
+                panic!("Don't know how to read {}", name);
+            }
+            files.push(name);
+        }
+        // Every file was checked, so a new one needs adding here as well.
+        files.sort();
+        let expected = [
+            "annotated.txt",
+            "blank.ss",
+            "candidate_line.ss",
+            "easy_solve.ss",
+            "inkala.sdk",
+            "possibles.txt",
+            "simple.ss",
+            "solvable.txt",
+            "solved.ss",
+            "sparse.ss",
+            "top95.sdm",
+            "top95.txt",
+            "xwing.ss",
+            "xwing_ready.cand",
+            "xwing_ready.ss",
+        ];
+        let expected: Vec<String> = expected
+            .iter()
+            .map(|name| format!("test/{}", name))
+            .collect();
+        assert_eq!(files, expected);
+    }
+
+    #[test]
+    fn test_colour() {
+        // Auto goes by whether the writer says it's a terminal.
+        assert!(Colour::Auto.enabled(true));
+        assert!(!Colour::Auto.enabled(false));
+        assert!(Colour::Always.enabled(false));
+        assert!(!Colour::Never.enabled(true));
+        for colour in COLOURS {
+            assert_eq!(Colour::from_name(colour.name()), Some(colour));
+        }
+        assert_eq!(Colour::from_name("sometimes"), None);
+    }
+
+    #[test]
+    fn test_write_possibles() {
+        let before = Sudoku::from_possibles("test/xwing_ready.ss".to_string());
+        let mut after = before;
+        solvers::xwing(&mut after);
+        assert_ne!(before, after);
+
+        // Only coloured in when asked.
+        let mut plain = String::new();
+        after
+            .write_possibles(&mut plain, Some(before), None, false)
+            .unwrap();
+        assert!(!plain.contains('\x1b'));
+        assert_eq!(plain, after.to_possibles_string());
+
+        let mut coloured = String::new();
+        after
+            .write_possibles(
+                &mut coloured,
+                Some(before),
+                Some("X Wing".to_string()),
+                true,
+            )
+            .unwrap();
+        assert!(coloured.starts_with("╔═══════════╦═══════════╦═══════════╗ X Wing\n"));
+        assert!(coloured.contains("\x1b[93m"));
+        assert_eq!(
+            coloured.replace("\x1b[93m", "").replace("\x1b[0m", ""),
+            plain.replacen('\n', " X Wing\n", 1)
+        );
+
+        let mut bytes = Vec::new();
+        after
+            .write_possibles_io(&mut bytes, Some(before), None, false)
+            .unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), plain);
+    }
+
+    #[test]
+    fn test_write_ss() {
+        let sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        let mut text = String::new();
+        sudoku.write_ss(&mut text).unwrap();
+
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 13);
+        assert_eq!(lines[0], "-----------");
+        assert_eq!(lines[1], "...|26.|7.1");
+        assert_eq!(lines[12], "-----------");
+
+        let mut bytes = Vec::new();
+        sudoku.write_ss_io(&mut bytes).unwrap();
+        assert_eq!(bytes, text.into_bytes());
     }
 }
@@ -1,7 +1,16 @@
+use crate::bitboard::BitBoard;
 use crate::constants::*;
+use crate::houses::*;
+use crate::rating::*;
+use crate::sk_box::*;
 use crate::sudoku::*;
+use crate::transform::Transform;
 use rand::prelude::*;
 use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
 
 // Use ChaCha as it can be seeded to be consistent between runs regardless of OS
 // and regardless of other versions.
@@ -11,6 +20,7 @@ use rand::seq::SliceRandom;
 // and then removing otehr random parts to quickly generate a large variety of
 // sudoku's - some of which may even be solvable but all of which should be legally
 // constructed.
+#[cfg(test)]
 fn get_9_rands(rng: &mut dyn RngCore) -> [u8; 9] {
     let mut result = ARRAY_OF_9;
 
@@ -21,6 +31,7 @@ fn get_9_rands(rng: &mut dyn RngCore) -> [u8; 9] {
 
 // Build up random sudoku by filling out some parts of it randomly then trying to
 // solve it. Useful for regression testing.
+#[cfg(test)]
 fn build_rand_sud(rng: &mut dyn RngCore) -> Sudoku {
     let mut sud = BLANK_SUDOKU;
 
@@ -30,13 +41,473 @@ fn build_rand_sud(rng: &mut dyn RngCore) -> Sudoku {
     sud.cells[MID_MID].set(rands);
     sud.cells[BOT_RHT].set(rands);
 
-    return sud;
+    sud
+}
+
+/**
+ * generate_solution_grid
+ *
+ * Make a complete, valid, random grid. Fills the boxes in order from the top left,
+ * trying the values each box could take in a shuffled order and backing up when a
+ * box runs out of options, then moves the result around with a random Transform.
+ *
+ * This doesn't pick evenly from every possible grid. Backing up finds some
+ * essentially different grids (ones no relabelling, reordering or flip turns into
+ * each other) more often than others, and the transform can't fix that as it only
+ * picks evenly between the versions of whichever grid was found. That's plenty
+ * random for making puzzles, but not for anything counting grids. Everything random
+ * comes from the rng, so given a seeded ChaCha8Rng the same seed gives the same grid
+ * on any machine.
+ */
+pub fn generate_solution_grid(rng: &mut dyn RngCore) -> Sudoku {
+    let mut values = [0u8; 81];
+    assert!(fill_grid(&mut values, 0, rng));
+    let values = Transform::random(rng).apply(&values);
+
+    let mut sud = BLANK_SUDOKU;
+    for (idx, &value) in values.iter().enumerate() {
+        *sud.box_at_mut(idx) = Box::from_val(value);
+    }
+
+    sud
+}
+
+fn fill_grid(values: &mut [u8; 81], idx: usize, rng: &mut dyn RngCore) -> bool {
+    if idx == 81 {
+        return true;
+    }
+
+    let mut options = ARRAY_OF_9;
+    options.shuffle(rng);
+
+    for value in options {
+        if PEERS[idx].iter().all(|&peer| values[peer] != value) {
+            values[idx] = value;
+            if fill_grid(values, idx + 1, rng) {
+                return true;
+            }
+        }
+    }
+
+    values[idx] = 0;
+    false
+}
+
+/**
+ * Symmetry
+ *
+ * Which boxes get taken out together when making a puzzle, so the givens left make
+ * a pattern like most printed sudokus. None takes them out one at a time. The
+ * rotations turn the grid by 180 or 90 degrees, the mirrors flip it top to bottom
+ * (Horizontal) or left to right (Vertical), and the diagonals flip it over the line
+ * from the top left (Diagonal) or the top right (AntiDiagonal) corner.
+ */
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Symmetry {
+    None,
+    Rotate180,
+    Rotate90,
+    Horizontal,
+    Vertical,
+    Diagonal,
+    AntiDiagonal,
+}
+
+pub const SYMMETRIES: [Symmetry; 7] = [
+    Symmetry::None,
+    Symmetry::Rotate180,
+    Symmetry::Rotate90,
+    Symmetry::Horizontal,
+    Symmetry::Vertical,
+    Symmetry::Diagonal,
+    Symmetry::AntiDiagonal,
+];
+
+impl Symmetry {
+    // Short name used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Symmetry::None => "none",
+            Symmetry::Rotate180 => "180",
+            Symmetry::Rotate90 => "90",
+            Symmetry::Horizontal => "horizontal",
+            Symmetry::Vertical => "vertical",
+            Symmetry::Diagonal => "diagonal",
+            Symmetry::AntiDiagonal => "antidiagonal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Symmetry> {
+        SYMMETRIES
+            .iter()
+            .copied()
+            .find(|symmetry| symmetry.name() == name)
+    }
+
+    // The box each box maps onto under the symmetry. Doing this over and over on
+    // a box walks round everything it has to be removed with.
+    fn partner(self, idx: usize) -> usize {
+        let row = idx / 9;
+        let col = idx % 9;
+        let (row, col) = match self {
+            Symmetry::None => (row, col),
+            Symmetry::Rotate180 => (8 - row, 8 - col),
+            Symmetry::Rotate90 => (col, 8 - row),
+            Symmetry::Horizontal => (8 - row, col),
+            Symmetry::Vertical => (row, 8 - col),
+            Symmetry::Diagonal => (col, row),
+            Symmetry::AntiDiagonal => (8 - col, 8 - row),
+        };
+        row * 9 + col
+    }
+
+    // Split all 81 boxes into the groups that have to be removed together.
+    fn groups(self) -> Vec<Vec<usize>> {
+        let mut result: Vec<Vec<usize>> = Vec::new();
+        let mut done = [false; 81];
+        for idx in 0..81 {
+            let mut group = Vec::new();
+            let mut next = idx;
+            while !done[next] {
+                done[next] = true;
+                group.push(next);
+                next = self.partner(next);
+            }
+            if !group.is_empty() {
+                result.push(group);
+            }
+        }
+        result
+    }
+}
+
+/**
+ * has_unique_solution
+ *
+ * True if there's exactly one way to finish the sudoku.
+ */
+pub fn has_unique_solution(sudoku: &Sudoku) -> bool {
+    BitBoard::from_sudoku(sudoku).count_solutions(2) == 1
+}
+
+/**
+ * generate_minimal
+ *
+ * Make a puzzle from the seed. Starts with a random full grid and takes the boxes
+ * out a group at a time in a random order, putting them back if the puzzle stops
+ * having just the one answer. What's left is minimal - no group can come out
+ * without breaking it. With Symmetry::None that means no single given can go.
+ */
+pub fn generate_minimal(seed: u64, symmetry: Symmetry) -> Sudoku {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let grid = generate_solution_grid(&mut rng);
+    make_minimal(&grid, symmetry, &mut rng)
+}
+
+/**
+ * make_minimal
+ *
+ * The removal half of generate_minimal, for any sudoku that has a unique answer.
+ */
+pub fn make_minimal(sudoku: &Sudoku, symmetry: Symmetry, rng: &mut dyn RngCore) -> Sudoku {
+    let mut puzzle = givens_only(sudoku);
+    assert!(has_unique_solution(&puzzle));
+
+    let mut groups = symmetry.groups();
+    groups.shuffle(rng);
+
+    for group in groups {
+        let before = puzzle;
+        for &idx in group.iter() {
+            *puzzle.box_at_mut(idx) = BLANK_BOX;
+        }
+        if !has_unique_solution(&puzzle) {
+            puzzle = before;
+        }
+    }
+
+    // Just the givens left, as normalise would go on to fill in any singles.
+    puzzle
+}
+
+/**
+ * Target
+ *
+ * What sort of puzzle generate_rated is after. The hardest technique a puzzle needs
+ * has to be in the band, and if needs is set that technique has to get used
+ * somewhere in the solve.
+ */
+#[derive(PartialEq, Debug, Clone)]
+pub struct Target {
+    pub band: RangeInclusive<Technique>,
+    pub needs: Option<Technique>,
+}
+
+impl Target {
+    pub fn accepts(&self, rating: &Rating) -> bool {
+        let needed = match self.needs {
+            Some(technique) => rating.uses(technique),
+            None => true,
+        };
+        needed && self.band.contains(&rating.hardest)
+    }
+}
+
+/**
+ * Budget
+ *
+ * How long generate_rated can keep trying, as a number of puzzles and a time
+ * limit. Whichever runs out first.
+ */
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Budget {
+    pub attempts: usize,
+    pub time: Duration,
+}
+
+/**
+ * generate_rated
+ *
+ * Keep making minimal puzzles from the seed until one hits the target, or give up
+ * when the budget runs out. The same seed runs through the same puzzles in the same
+ * order, so it'll find the same one again as long as the budget stretches that far.
+ */
+pub fn generate_rated(
+    seed: u64,
+    symmetry: Symmetry,
+    target: &Target,
+    budget: &Budget,
+) -> Option<(Sudoku, Rating)> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let start = Instant::now();
+
+    for _ in 0..budget.attempts {
+        if start.elapsed() >= budget.time {
+            break;
+        }
+
+        let grid = generate_solution_grid(&mut rng);
+        let puzzle = make_minimal(&grid, symmetry, &mut rng);
+        if let Some(rating) = rate(&puzzle) {
+            if target.accepts(&rating) {
+                return Some((puzzle, rating));
+            }
+        }
+    }
+
+    None
+}
+
+/**
+ * Mask
+ *
+ * Which boxes have to be givens, as row-major indexes from 0 to 80.
+ */
+pub type Mask = [bool; 81];
+
+/**
+ * read_mask
+ *
+ * Read a mask from a file laid out like a .ss file, with an 'x' for every given and
+ * a '.' for every blank. The '|' and '-' lines are optional, like below:
+ *
+ * |.xx|x.x|xx.|
+ * |x..|.x.|..x|
+ * ...
+ */
+pub fn read_mask(filename: String) -> Result<Mask, &'static str> {
+    let text = match fs::read_to_string(filename) {
+        Ok(text) => text,
+        Err(_) => return Err("Could not read mask file"),
+    };
+
+    let mut mask = [false; 81];
+    let mut idx = 0;
+    for line in text.lines() {
+        // Skip the lines between rows of cells.
+        if line.trim().chars().all(|c| c == '-') {
+            continue;
+        }
+
+        for c in line.chars() {
+            let given = match c {
+                'x' | 'X' => true,
+                '.' => false,
+                '|' | ' ' | '\t' => continue,
+                _ => return Err("Expected 'x' or '.' in mask"),
+            };
+            if idx == 81 {
+                return Err("Too many boxes in mask");
+            }
+            mask[idx] = given;
+            idx += 1;
+        }
+    }
+
+    if idx != 81 {
+        return Err("Expected 81 boxes in mask");
+    }
+    Ok(mask)
+}
+
+/**
+ * generate_from_mask
+ *
+ * Find a puzzle with givens in exactly the boxes of the mask and one answer.
+ *
+ * Picking random grids until one fits hardly ever works for the sparse masks people
+ * like drawing, so this starts from one random grid and keeps nudging it. Each try
+ * throws away the values of a few of the mask's boxes and fills the grid back in
+ * randomly around the rest. If the new grid gives a puzzle with no more answers
+ * than before (or as many, but agreeing on more boxes) it's kept, otherwise it's
+ * back to the old one. Counts go up to SOLUTION_CAP so every try stays quick, and
+ * the givens that get changed are picked from ones near where the answers disagree.
+ *
+ * Gives up with None once the budget runs out.
+ */
+pub fn generate_from_mask(seed: u64, mask: &Mask, budget: &Budget) -> Option<Sudoku> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let start = Instant::now();
+
+    let clues: Vec<usize> = (0..81).filter(|&idx| mask[idx]).collect();
+    if clues.is_empty() {
+        return None;
+    }
+
+    let mut grid = [0u8; 81];
+    assert!(complete_grid(&mut grid, &mut rng));
+    let mut solutions = masked_solutions(&grid, mask);
+
+    for _ in 0..budget.attempts {
+        if solutions.len() == 1 {
+            return Some(masked_puzzle(&grid, mask));
+        }
+        if start.elapsed() >= budget.time {
+            break;
+        }
+
+        // The boxes that differ between two answers are what needs pinning down, so
+        // prefer changing the givens that can see them.
+        let differ: Vec<usize> = (0..81)
+            .filter(|&idx| solutions[0].values[idx] != solutions[1].values[idx])
+            .collect();
+        let near: Vec<usize> = clues
+            .iter()
+            .copied()
+            .filter(|&idx| differ.iter().any(|other| PEERS[idx].contains(other)))
+            .collect();
+        let pick_from = if near.is_empty() { &clues } else { &near };
+
+        let mut next = [0u8; 81];
+        for &idx in clues.iter() {
+            next[idx] = grid[idx];
+        }
+        let changes = rng.gen_range(1..=3.min(pick_from.len()));
+        for &idx in pick_from.choose_multiple(&mut rng, changes) {
+            next[idx] = 0;
+        }
+        assert!(complete_grid(&mut next, &mut rng));
+
+        // Keep anything no worse, and now and then something a bit worse so it
+        // doesn't get stuck.
+        let next_solutions = masked_solutions(&next, mask);
+        let score = ambiguity(&solutions);
+        let next_score = ambiguity(&next_solutions);
+        let bit_worse = next_score.0 < SOLUTION_CAP && next_score.0 <= score.0 * 2;
+        if next_score <= score || (bit_worse && rng.gen_ratio(1, 10)) {
+            grid = next;
+            solutions = next_solutions;
+        }
+    }
+
+    if solutions.len() == 1 {
+        return Some(masked_puzzle(&grid, mask));
+    }
+    None
+}
+
+const SOLUTION_CAP: usize = 100;
+
+// How far a set of answers is from being just the one. Mostly how many there are,
+// but as that stops at SOLUTION_CAP also how many boxes they don't agree on.
+fn ambiguity(solutions: &[BitBoard]) -> (usize, usize) {
+    let differ = (0..81)
+        .filter(|&idx| {
+            solutions
+                .iter()
+                .any(|s| s.values[idx] != solutions[0].values[idx])
+        })
+        .count();
+    (solutions.len(), differ)
+}
+
+fn masked_solutions(grid: &[u8; 81], mask: &Mask) -> Vec<BitBoard> {
+    BitBoard::from_sudoku(&masked_puzzle(grid, mask)).solutions(SOLUTION_CAP)
+}
+
+// Just the boxes of the grid that are in the mask, as a puzzle. Nothing else gets
+// touched, as normalise would fill in singles outside the mask.
+fn masked_puzzle(grid: &[u8; 81], mask: &Mask) -> Sudoku {
+    let mut result = BLANK_SUDOKU;
+    for idx in 0..81 {
+        if mask[idx] {
+            *result.box_at_mut(idx) = Box::from_val(grid[idx]);
+        }
+    }
+    result
+}
+
+// Randomly fill in every 0 left in a grid, always doing the box with the fewest
+// options next so grids with a lot already filled in don't take forever.
+fn complete_grid(values: &mut [u8; 81], rng: &mut dyn RngCore) -> bool {
+    let mut best: Option<(usize, Vec<u8>)> = None;
+    for idx in 0..81 {
+        if values[idx] != 0 {
+            continue;
+        }
+        let options: Vec<u8> = (1..=9)
+            .filter(|&value| PEERS[idx].iter().all(|&peer| values[peer] != value))
+            .collect();
+        let fewer = match &best {
+            Some((_, best_options)) => options.len() < best_options.len(),
+            None => true,
+        };
+        if fewer {
+            best = Some((idx, options));
+        }
+    }
+
+    let (idx, mut options) = match best {
+        Some(best) => best,
+        None => return true,
+    };
+
+    options.shuffle(rng);
+    for value in options {
+        values[idx] = value;
+        if complete_grid(values, rng) {
+            return true;
+        }
+    }
+
+    values[idx] = 0;
+    false
+}
+
+// Strip a sudoku back to just its solved boxes, like it had been read in fresh.
+fn givens_only(sudoku: &Sudoku) -> Sudoku {
+    let mut result = BLANK_SUDOKU;
+    for idx in 0..81 {
+        if let Some(value) = sudoku.box_at(idx).value {
+            *result.box_at_mut(idx) = Box::from_val(value);
+        }
+    }
+    result
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rand_chacha::*;
 
     #[test]
     fn test_9_rands() {
@@ -61,4 +532,211 @@ mod tests {
         assert_eq!(sud.cells[TOP_LFT].boxes[TOP_LFT].value.unwrap(), 4);
         assert_eq!(sud.cells[BOT_RHT].boxes[BOT_RHT].value.unwrap(), 7);
     }
+
+    #[test]
+    fn test_generate_solution_grid() {
+        let mut rng: ChaCha8Rng = ChaCha8Rng::seed_from_u64(0);
+        let sud = generate_solution_grid(&mut rng);
+
+        assert!(sud.solved());
+        for house in HOUSES.iter() {
+            let mut seen = [false; 10];
+            for &idx in house.iter() {
+                let value = sud.box_at(idx).value.unwrap() as usize;
+                assert!(!seen[value]);
+                seen[value] = true;
+            }
+        }
+
+        // Pinned so we notice if the same seed ever stops giving the same grid.
+        assert_eq!(
+            sud.to_line_string(),
+            "467238159913564872825719643649187235581423967732956481258341796374692518196875324"
+        );
+
+        let mut rng: ChaCha8Rng = ChaCha8Rng::seed_from_u64(0);
+        assert_eq!(generate_solution_grid(&mut rng), sud);
+        assert_ne!(generate_solution_grid(&mut rng), sud);
+    }
+
+    fn givens(sudoku: &Sudoku) -> Vec<usize> {
+        (0..81)
+            .filter(|&idx| sudoku.box_at(idx).value.is_some())
+            .collect()
+    }
+
+    #[test]
+    fn test_generate_minimal() {
+        let puzzle = generate_minimal(1, Symmetry::None);
+        assert!(has_unique_solution(&puzzle));
+        assert_eq!(puzzle, generate_minimal(1, Symmetry::None));
+        assert_ne!(puzzle, generate_minimal(2, Symmetry::None));
+
+        // Taking out any one of the givens that are left gives more than one answer,
+        // and nothing gets filled in on the way out.
+        for seed in 1..=5 {
+            let puzzle = generate_minimal(seed, Symmetry::None);
+            assert_eq!(puzzle, givens_only(&puzzle));
+            for idx in givens(&puzzle) {
+                let mut less = puzzle;
+                *less.box_at_mut(idx) = BLANK_BOX;
+                assert!(
+                    !has_unique_solution(&less),
+                    "seed {} r{}c{}",
+                    seed,
+                    idx / 9 + 1,
+                    idx % 9 + 1
+                );
+            }
+        }
+
+        // And solving it gives back the grid it started from.
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let grid = generate_solution_grid(&mut rng);
+        let mut solved = puzzle;
+        solved.solve(None);
+        assert!(solved.solved());
+        assert_eq!(solved.to_line_string(), grid.to_line_string());
+    }
+
+    #[test]
+    fn test_generate_symmetric() {
+        for symmetry in SYMMETRIES {
+            let puzzle = generate_minimal(7, symmetry);
+            assert!(has_unique_solution(&puzzle));
+
+            let givens = givens(&puzzle);
+            for &idx in givens.iter() {
+                assert!(givens.contains(&symmetry.partner(idx)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_symmetry_groups() {
+        // Every box turns up in exactly one group.
+        for symmetry in SYMMETRIES {
+            let mut all: Vec<usize> = symmetry.groups().concat();
+            all.sort();
+            assert_eq!(all, (0..81).collect::<Vec<usize>>());
+        }
+
+        assert_eq!(Symmetry::from_name("90"), Some(Symmetry::Rotate90));
+        assert_eq!(Symmetry::from_name("sideways"), None);
+
+        assert_eq!(Symmetry::None.groups().len(), 81);
+        // The middle box stays put, the rest pair up.
+        assert_eq!(Symmetry::Rotate180.groups().len(), 41);
+        // Middle on its own and the rest in fours.
+        assert_eq!(Symmetry::Rotate90.groups().len(), 21);
+        // The middle row stays put.
+        assert_eq!(Symmetry::Horizontal.groups().len(), 45);
+        assert!(Symmetry::Diagonal.groups().contains(&vec![1, 9]));
+        assert!(Symmetry::AntiDiagonal.groups().contains(&vec![0, 80]));
+        assert_eq!(Symmetry::Rotate90.groups()[0], vec![0, 8, 80, 72]);
+    }
+
+    #[test]
+    fn test_generate_rated() {
+        let budget = Budget {
+            attempts: 100,
+            time: Duration::from_secs(60),
+        };
+
+        let hard = Target {
+            band: Technique::NakedSet..=Technique::UnitForcingChain,
+            needs: None,
+        };
+        let (puzzle, rating) = generate_rated(3, Symmetry::None, &hard, &budget).unwrap();
+        assert!(rating.hardest >= Technique::NakedSet);
+        assert_eq!(rate(&puzzle), Some(rating));
+        assert!(has_unique_solution(&puzzle));
+
+        let easy = Target {
+            band: Technique::SinglePosition..=Technique::SinglePosition,
+            needs: None,
+        };
+        let (puzzle, rating) = generate_rated(3, Symmetry::None, &easy, &budget).unwrap();
+        assert_eq!(rating.used, vec![Technique::SinglePosition]);
+        assert_eq!(
+            generate_rated(3, Symmetry::None, &easy, &budget).unwrap().0,
+            puzzle
+        );
+
+        // Nothing can be found with no time at all.
+        let no_time = Budget {
+            attempts: 100,
+            time: Duration::ZERO,
+        };
+        assert_eq!(generate_rated(3, Symmetry::None, &easy, &no_time), None);
+    }
+
+    #[test]
+    fn test_target_needs() {
+        let rating = Rating {
+            hardest: Technique::DigitNishio,
+            used: vec![
+                Technique::SinglePosition,
+                Technique::XWing,
+                Technique::DigitNishio,
+            ],
+        };
+        let mut target = Target {
+            band: Technique::SinglePosition..=Technique::UnitForcingChain,
+            needs: Some(Technique::XWing),
+        };
+        assert!(target.accepts(&rating));
+
+        target.needs = Some(Technique::NakedSet);
+        assert!(!target.accepts(&rating));
+
+        target.needs = None;
+        target.band = Technique::SinglePosition..=Technique::XWing;
+        assert!(!target.accepts(&rating));
+    }
+
+    #[test]
+    fn test_read_mask() {
+        let mask = read_mask("test/heart.mask".to_string()).unwrap();
+        assert_eq!(mask.iter().filter(|&&given| given).count(), 21);
+        assert!(!mask[0] && mask[1] && mask[2]);
+        assert!(mask[76]);
+
+        assert!(read_mask("test/simple.ss".to_string()).is_err());
+        assert!(read_mask("test/missing.mask".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_generate_from_mask() {
+        let mask = read_mask("test/lattice.mask".to_string()).unwrap();
+        let budget = Budget {
+            attempts: 2000,
+            time: Duration::from_secs(120),
+        };
+
+        let puzzle = generate_from_mask(1, &mask, &budget).unwrap();
+        assert!(has_unique_solution(&puzzle));
+        for (idx, &given) in mask.iter().enumerate() {
+            assert_eq!(puzzle.box_at(idx).value.is_some(), given);
+        }
+
+        // With every box but one a given, the rest would follow straight away, but
+        // the puzzle still only has givens where the mask says.
+        let mut mask = [true; 81];
+        mask[0] = false;
+        let puzzle = generate_from_mask(1, &mask, &budget).unwrap();
+        let clues: Vec<bool> = (0..81)
+            .map(|idx| puzzle.box_at(idx).value.is_some())
+            .collect();
+        assert_eq!(clues, mask.to_vec());
+
+        // No puzzle has a single given, so it runs out of tries.
+        let mut mask = [false; 81];
+        mask[40] = true;
+        let budget = Budget {
+            attempts: 20,
+            time: Duration::from_secs(120),
+        };
+        assert_eq!(generate_from_mask(1, &mask, &budget), None);
+    }
 }
@@ -0,0 +1,241 @@
+/*
+ * Drawing sudokus as SVG, for printing.
+ *
+ * The grid has thin lines between boxes and thick ones around each cell. Digits
+ * given in the puzzle are drawn bold and black, anything solved after that in blue,
+ * so a solution can be printed without losing track of what the puzzle was. The
+ * look is all in CSS classes at the top of the file so it can be changed without
+ * touching this:
+ *
+ * given     - a digit given in the puzzle
+ * solved    - a digit worked out since
+ * mark      - a small possible value in an unsolved box
+ * highlight - the background of a highlighted box
+ */
+use crate::sudoku::Sudoku;
+use std::fmt::Write;
+
+// Size of a box, and the gap around the grid, in pixels.
+pub const BOX_SIZE: usize = 40;
+pub const MARGIN: usize = 10;
+
+pub const STYLE: &str = "
+.given { font: bold 28px sans-serif; fill: black; }
+.solved { font: 28px sans-serif; fill: #2a5db0; }
+.mark { font: 11px sans-serif; fill: #555555; }
+.highlight { fill: #fff1a8; }
+.thin { stroke: #888888; stroke-width: 1; }
+.thick { stroke: black; stroke-width: 3; }
+";
+
+/**
+ * SvgOptions
+ *
+ * What to draw besides the values.
+ *
+ * givens       - The puzzle as it started, so anything solved since can be drawn
+ *                differently. Without it every value is drawn as a given.
+ * pencil_marks - Draw the possible values of unsolved boxes small, in the same 3x3
+ *                layout as print_possibles.
+ * highlight    - Boxes to shade, as indexes from 0 to 80 left to right and top to
+ *                bottom.
+ */
+#[derive(Clone, Default)]
+pub struct SvgOptions {
+    pub givens: Option<Sudoku>,
+    pub pencil_marks: bool,
+    pub highlight: Vec<usize>,
+}
+
+/**
+ * parse_box_name
+ *
+ * Turn a box name like r1c9 (row 1, column 9) into its index from 0 to 80.
+ */
+pub fn parse_box_name(name: &str) -> Option<usize> {
+    let name = name.trim().to_lowercase();
+    let (row, col) = name.strip_prefix('r')?.split_once('c')?;
+    let row: usize = row.parse().ok()?;
+    let col: usize = col.parse().ok()?;
+    if !(1..=9).contains(&row) || !(1..=9).contains(&col) {
+        return None;
+    }
+    Some((row - 1) * 9 + col - 1)
+}
+
+/**
+ * render
+ *
+ * The whole sudoku as a standalone SVG document.
+ */
+pub fn render(sudoku: &Sudoku, options: &SvgOptions) -> String {
+    let size = BOX_SIZE * 9 + MARGIN * 2;
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">",
+        size
+    )
+    .unwrap();
+    writeln!(svg, "<style>{}</style>", STYLE).unwrap();
+    writeln!(
+        svg,
+        "<rect x=\"0\" y=\"0\" width=\"{0}\" height=\"{0}\" fill=\"white\"/>",
+        size
+    )
+    .unwrap();
+    svg.push_str(&render_grid(sudoku, options, MARGIN, MARGIN));
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/**
+ * render_grid
+ *
+ * Just the grid, with its top left corner at x, y, to go inside a bigger SVG.
+ */
+pub fn render_grid(sudoku: &Sudoku, options: &SvgOptions, x: usize, y: usize) -> String {
+    let mut svg = String::new();
+    writeln!(svg, "<g transform=\"translate({} {})\">", x, y).unwrap();
+
+    // Shading first so the lines and digits go over it.
+    for &idx in options.highlight.iter() {
+        writeln!(
+            svg,
+            "<rect class=\"highlight\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+            (idx % 9) * BOX_SIZE,
+            (idx / 9) * BOX_SIZE,
+            BOX_SIZE,
+            BOX_SIZE
+        )
+        .unwrap();
+    }
+
+    for idx in 0..81 {
+        let left = (idx % 9) * BOX_SIZE;
+        let top = (idx / 9) * BOX_SIZE;
+        let sk_box = sudoku.box_at(idx);
+
+        match sk_box.value {
+            Some(value) => {
+                let given = match options.givens {
+                    Some(givens) => givens.box_at(idx).value == Some(value),
+                    None => true,
+                };
+                writeln!(
+                    svg,
+                    "<text class=\"{}\" x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                    if given { "given" } else { "solved" },
+                    left + BOX_SIZE / 2,
+                    top + BOX_SIZE / 2,
+                    value
+                )
+                .unwrap();
+            }
+            None if options.pencil_marks => {
+                // Each value gets its own spot in a 3x3 grid inside the box.
+                let spot = BOX_SIZE / 3;
+                for value in 1..=9 {
+                    if sk_box.is_poss(value) {
+                        let value_col = (value as usize - 1) % 3;
+                        let value_row = (value as usize - 1) / 3;
+                        writeln!(
+                            svg,
+                            "<text class=\"mark\" x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                            left + value_col * spot + spot / 2,
+                            top + value_row * spot + spot / 2,
+                            value
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+
+    // Thin lines first so the thick ones are drawn on top where they cross.
+    let end = BOX_SIZE * 9;
+    for thick in [false, true] {
+        for line in 0..=9 {
+            if (line % 3 == 0) != thick {
+                continue;
+            }
+            let at = line * BOX_SIZE;
+            let class = if thick { "thick" } else { "thin" };
+            writeln!(
+                svg,
+                "<line class=\"{0}\" x1=\"{1}\" y1=\"0\" x2=\"{1}\" y2=\"{2}\"/>",
+                class, at, end
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                "<line class=\"{0}\" x1=\"0\" y1=\"{1}\" x2=\"{2}\" y2=\"{1}\"/>",
+                class, at, end
+            )
+            .unwrap();
+        }
+    }
+
+    svg.push_str("</g>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_box_name() {
+        assert_eq!(parse_box_name("r1c1"), Some(0));
+        assert_eq!(parse_box_name("R2C3"), Some(11));
+        assert_eq!(parse_box_name("r9c9"), Some(80));
+        assert_eq!(parse_box_name("r0c1"), None);
+        assert_eq!(parse_box_name("c1r1"), None);
+        assert_eq!(parse_box_name("r1"), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let puzzle = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        let givens = puzzle.values().iter().filter(|&&value| value != 0).count();
+
+        let svg = render(&puzzle, &SvgOptions::default());
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("class=\"given\"").count(), givens);
+        assert_eq!(svg.matches("class=\"thick\"").count(), 8);
+        assert_eq!(svg.matches("class=\"thin\"").count(), 12);
+        assert_eq!(svg.matches("class=\"mark\"").count(), 0);
+
+        let mut solution = puzzle;
+        solution.solve(None);
+        let options = SvgOptions {
+            givens: Some(puzzle),
+            highlight: vec![0, 80],
+            ..SvgOptions::default()
+        };
+        let svg = render(&solution, &options);
+        assert_eq!(svg.matches("class=\"given\"").count(), givens);
+        assert_eq!(svg.matches("class=\"solved\"").count(), 81 - givens);
+        assert_eq!(svg.matches("<rect class=\"highlight\"").count(), 2);
+    }
+
+    #[test]
+    fn test_render_pencil_marks() {
+        let sudoku = Sudoku::from_possibles("test/xwing_ready.ss".to_string());
+        let marks: usize = (0..81)
+            .filter(|&idx| !sudoku.box_at(idx).solved())
+            .map(|idx| sudoku.box_at(idx).get_possibles().len())
+            .sum();
+
+        let options = SvgOptions {
+            pencil_marks: true,
+            ..SvgOptions::default()
+        };
+        let svg = render(&sudoku, &options);
+        assert_eq!(svg.matches("class=\"mark\"").count(), marks);
+    }
+}
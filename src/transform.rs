@@ -0,0 +1,577 @@
+/*
+ * Moving a sudoku around without changing it in any way that matters.
+ *
+ * Swapping two rows in the same band, two bands, two columns in the same stack or
+ * two stacks, flipping the grid over its diagonal and swapping digits around all
+ * give back a sudoku that's really the same puzzle - same number of answers, same
+ * techniques needed to solve it. Any mix of those can be written down as one
+ * Transform.
+ *
+ * A band is a row of 3 cells, a stack a column of 3 cells.
+ */
+use crate::sudoku::*;
+use rand::prelude::*;
+
+/**
+ * Transform
+ *
+ * Box (row, col) of the result is box (rows[row], cols[col]) of the original, after
+ * it's been flipped over the diagonal if transpose is set. The value in it then
+ * becomes digits[value]. digits[0] is always 0 so blanks stay blank.
+ */
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct Transform {
+    pub transpose: bool,
+    pub rows: [usize; 9],
+    pub cols: [usize; 9],
+    pub digits: [u8; 10],
+}
+
+pub const IDENTITY: Transform = Transform {
+    transpose: false,
+    rows: [0, 1, 2, 3, 4, 5, 6, 7, 8],
+    cols: [0, 1, 2, 3, 4, 5, 6, 7, 8],
+    digits: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+};
+
+// Every order of 3 things.
+const ORDERS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+impl Transform {
+    /**
+     * apply
+     *
+     * Move the values of a row-major grid about. Works on values alone, a 0 being a
+     * blank.
+     */
+    pub fn apply(&self, values: &[u8; 81]) -> [u8; 81] {
+        assert!(self.is_valid());
+        let mut result = [0; 81];
+        for (idx, value) in result.iter_mut().enumerate() {
+            *value = self.digits[values[self.source(idx)] as usize];
+        }
+        result
+    }
+
+    /**
+     * source
+     *
+     * Which box of the original ends up at the given box, both row-major indexes.
+     */
+    pub fn source(&self, idx: usize) -> usize {
+        let (row, col) = (idx / 9, idx % 9);
+        if self.transpose {
+            self.cols[col] * 9 + self.rows[row]
+        } else {
+            self.rows[row] * 9 + self.cols[col]
+        }
+    }
+
+    /**
+     * is_valid
+     *
+     * Check the transform really is one that keeps a sudoku the same - rows only
+     * move around within their band, bands only swap as a whole, and the same for
+     * columns, and the digits are just swapped around.
+     */
+    pub fn is_valid(&self) -> bool {
+        let mut digits = self.digits;
+        digits.sort();
+        digits == IDENTITY.digits
+            && self.digits[0] == 0
+            && is_line_order(&self.rows)
+            && is_line_order(&self.cols)
+    }
+
+    /**
+     * transpose
+     *
+     * Flip the grid over its diagonal, so rows become columns.
+     */
+    pub fn transpose() -> Transform {
+        Transform {
+            transpose: true,
+            ..IDENTITY
+        }
+    }
+
+    /**
+     * random
+     *
+     * Any of the transforms, all equally likely. Everything comes from the rng so a
+     * seeded one always gives the same answers.
+     */
+    pub fn random(rng: &mut dyn RngCore) -> Transform {
+        let orders = line_orders();
+        let mut digits = IDENTITY.digits;
+        digits[1..].shuffle(rng);
+
+        Transform {
+            transpose: rng.gen_bool(0.5),
+            rows: orders[rng.gen_range(0..orders.len())],
+            cols: orders[rng.gen_range(0..orders.len())],
+            digits,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Transform {
+    /**
+     * The rest of the simple moves, each as a transform of its own. Rows, columns,
+     * bands and stacks are numbered from 0. rotate turns the grid a quarter turn clockwise,
+     * mirror flips it left to right and flip top to bottom.
+     */
+    pub fn relabel(digits: [u8; 10]) -> Transform {
+        Transform { digits, ..IDENTITY }
+    }
+
+    pub fn swap_rows(a: usize, b: usize) -> Transform {
+        assert!(a / 3 == b / 3, "Rows have to be in the same band");
+        let mut rows = IDENTITY.rows;
+        rows.swap(a, b);
+        Transform { rows, ..IDENTITY }
+    }
+
+    pub fn swap_cols(a: usize, b: usize) -> Transform {
+        assert!(a / 3 == b / 3, "Columns have to be in the same stack");
+        let mut cols = IDENTITY.cols;
+        cols.swap(a, b);
+        Transform { cols, ..IDENTITY }
+    }
+
+    pub fn swap_bands(a: usize, b: usize) -> Transform {
+        let mut rows = IDENTITY.rows;
+        for i in 0..3 {
+            rows.swap(a * 3 + i, b * 3 + i);
+        }
+        Transform { rows, ..IDENTITY }
+    }
+
+    pub fn swap_stacks(a: usize, b: usize) -> Transform {
+        let mut cols = IDENTITY.cols;
+        for i in 0..3 {
+            cols.swap(a * 3 + i, b * 3 + i);
+        }
+        Transform { cols, ..IDENTITY }
+    }
+
+    pub fn rotate() -> Transform {
+        Transform {
+            transpose: true,
+            cols: [8, 7, 6, 5, 4, 3, 2, 1, 0],
+            ..IDENTITY
+        }
+    }
+
+    pub fn mirror() -> Transform {
+        Transform {
+            cols: [8, 7, 6, 5, 4, 3, 2, 1, 0],
+            ..IDENTITY
+        }
+    }
+
+    pub fn flip() -> Transform {
+        Transform {
+            rows: [8, 7, 6, 5, 4, 3, 2, 1, 0],
+            ..IDENTITY
+        }
+    }
+}
+
+// A valid order of rows or columns. Each group of 3 has to come from one band (or
+// stack), and all 9 have to be used.
+fn is_line_order(order: &[usize; 9]) -> bool {
+    let mut used = [false; 9];
+    for group in 0..3 {
+        let band = order[group * 3] / 3;
+        for i in 0..3 {
+            let line = order[group * 3 + i];
+            if line >= 9 || line / 3 != band || used[line] {
+                return false;
+            }
+            used[line] = true;
+        }
+    }
+    true
+}
+
+/**
+ * line_orders
+ *
+ * All 1296 ways of ordering rows (or columns) - 6 orders of the bands, times 6
+ * orders of the rows within each of the 3 bands.
+ */
+pub fn line_orders() -> Vec<[usize; 9]> {
+    let mut result = Vec::with_capacity(1296);
+    for bands in ORDERS {
+        for first in ORDERS {
+            for second in ORDERS {
+                for third in ORDERS {
+                    let mut order = [0; 9];
+                    for (i, within) in [first, second, third].iter().enumerate() {
+                        for j in 0..3 {
+                            order[i * 3 + j] = bands[i] * 3 + within[j];
+                        }
+                    }
+                    result.push(order);
+                }
+            }
+        }
+    }
+    result
+}
+
+/**
+ * canonical_transform
+ *
+ * The transform that turns the values into the smallest grid they can be, reading
+ * the boxes as one long number with 0 for blanks. Digits are always numbered in the
+ * order they turn up, so only the row and column moves need trying. Two grids are
+ * the same puzzle exactly when they come out the same.
+ *
+ * Trying all 2 x 1296 x 1296 arrangements is too slow, but the first row alone
+ * rules out most of them. Its digits always come out as 1, 2, 3... so all that
+ * matters is where its blanks end up, and only the rows and column orders that
+ * give the smallest first row need trying the rest of the way.
+ */
+pub fn canonical_transform(values: &[u8; 81]) -> Transform {
+    let orders = line_orders();
+    let grids = [*values, Transform::transpose().apply(values)];
+
+    // Every grid, first row and column order that gives the smallest first row.
+    let mut first_best = [10u8; 9];
+    let mut starts = Vec::new();
+    for (flipped, grid) in grids.iter().enumerate() {
+        for row in 0..9 {
+            for cols in orders.iter() {
+                let first = first_row(grid, row, cols);
+                if first < first_best {
+                    first_best = first;
+                    starts.clear();
+                }
+                if first == first_best {
+                    starts.push((flipped, row, cols));
+                }
+            }
+        }
+    }
+
+    let mut by_first = vec![Vec::new(); 9];
+    for rows in orders.iter() {
+        by_first[rows[0]].push(rows);
+    }
+
+    let mut best = [10u8; 81];
+    let mut result = IDENTITY;
+    for (flipped, row, cols) in starts {
+        for &rows in by_first[row].iter() {
+            if let Some(digits) = try_arrangement(&grids[flipped], rows, cols, &mut best) {
+                result = Transform {
+                    transpose: flipped == 1,
+                    rows: *rows,
+                    cols: *cols,
+                    digits,
+                };
+            }
+        }
+    }
+    result
+}
+
+// The given row laid out in the column order, its digits numbered as they come.
+fn first_row(grid: &[u8; 81], row: usize, cols: &[usize; 9]) -> [u8; 9] {
+    let mut result = [0; 9];
+    let mut next = 1;
+    for (value, &col) in result.iter_mut().zip(cols.iter()) {
+        if grid[row * 9 + col] != 0 {
+            *value = next;
+            next += 1;
+        }
+    }
+    result
+}
+
+// Lay the grid out with the given rows and columns, numbering digits as they come.
+// If that beats best it becomes the new best, and the numbering is given back with
+// any digits not in the grid numbered after the rest.
+fn try_arrangement(
+    grid: &[u8; 81],
+    rows: &[usize; 9],
+    cols: &[usize; 9],
+    best: &mut [u8; 81],
+) -> Option<[u8; 10]> {
+    let mut digits = [0u8; 10];
+    let mut next = 1;
+    let mut better = false;
+
+    for idx in 0..81 {
+        let value = grid[rows[idx / 9] * 9 + cols[idx % 9]] as usize;
+        let value = if value == 0 {
+            0
+        } else {
+            if digits[value] == 0 {
+                digits[value] = next;
+                next += 1;
+            }
+            digits[value]
+        };
+
+        if !better {
+            if value > best[idx] {
+                return None;
+            }
+            better = value < best[idx];
+        }
+        if better {
+            best[idx] = value;
+        }
+    }
+
+    if !better {
+        return None;
+    }
+    for digit in digits[1..].iter_mut().filter(|digit| **digit == 0) {
+        *digit = next;
+        next += 1;
+    }
+    Some(digits)
+}
+
+impl Sudoku {
+    /**
+     * canonical
+     *
+     * The same puzzle moved around into a standard form, so that any two sudokus
+     * that are just relabelled, rotated, swapped about and so on come out the same.
+     * Only the solved boxes count towards the form, but the possible values of the
+     * rest move along with them.
+     */
+    pub fn canonical(&self) -> Sudoku {
+        self.apply_transform(&canonical_transform(&self.values()))
+    }
+
+    /**
+     * apply_transform / transform
+     *
+     * Move the sudoku about by a transform, or a random one. Either way it's still the
+     * same puzzle underneath, just harder to recognise. Each box moves as it is,
+     * possible values and all, so nothing gets solved along the way.
+     */
+    pub fn apply_transform(&self, transform: &Transform) -> Sudoku {
+        assert!(transform.is_valid());
+        let mut result = *self;
+        for idx in 0..81 {
+            let from = self.box_at(transform.source(idx));
+            let to = result.box_at_mut(idx);
+            to.value = from.value.map(|value| transform.digits[value as usize]);
+            for value in 1..10 {
+                to.poss[transform.digits[value] as usize] = from.poss[value];
+            }
+        }
+        result
+    }
+
+    pub fn transform(&self, rng: &mut dyn RngCore) -> Sudoku {
+        self.apply_transform(&Transform::random(rng))
+    }
+
+    /**
+     * is_equivalent
+     *
+     * True if the other sudoku is this one moved around. Only the tests need it, as
+     * anything going through lots of sudokus is better off keeping the canonical
+     * forms.
+     */
+    #[cfg(test)]
+    pub fn is_equivalent(&self, other: &Sudoku) -> bool {
+        self.canonical().values() == other.canonical().values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: &str =
+        "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+    #[test]
+    fn test_line_orders() {
+        let orders = line_orders();
+        assert_eq!(orders.len(), 1296);
+        assert_eq!(orders[0], IDENTITY.rows);
+        assert!(orders.iter().all(is_line_order));
+
+        let mut unique = orders.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 1296);
+
+        assert!(!is_line_order(&[0, 1, 3, 2, 4, 5, 6, 7, 8]));
+        assert!(!is_line_order(&[0, 0, 2, 3, 4, 5, 6, 7, 8]));
+    }
+
+    #[test]
+    fn test_apply() {
+        let values = Sudoku::parse_line(LINE).unwrap().values();
+
+        let transpose = Transform {
+            transpose: true,
+            ..IDENTITY
+        };
+        assert!(transpose.is_valid());
+        let flipped = transpose.apply(&values);
+        // r2c4 of the flipped grid is r4c2 of the original.
+        assert_eq!(flipped[12], values[28]);
+        assert_eq!(transpose.apply(&flipped), values);
+
+        let swap = Transform {
+            rows: [3, 4, 5, 0, 1, 2, 6, 7, 8],
+            digits: [0, 2, 1, 3, 4, 5, 6, 7, 8, 9],
+            ..IDENTITY
+        };
+        assert!(swap.is_valid());
+        let swapped = swap.apply(&values);
+        // r1c1 was a 4 and moves to r4c1.
+        assert_eq!(swapped[27], 4);
+        for col in 0..9 {
+            assert_eq!(swapped[col], swap.digits[values[27 + col] as usize]);
+        }
+
+        assert!(!Transform {
+            digits: [0, 1, 1, 3, 4, 5, 6, 7, 8, 9],
+            ..IDENTITY
+        }
+        .is_valid());
+    }
+
+    #[test]
+    fn test_canonical() {
+        let sudoku = Sudoku::parse_line(LINE).unwrap();
+        let canonical = sudoku.canonical();
+
+        // Counts of givens don't change, and it's already as small as it gets.
+        let count = |s: &Sudoku| s.values().iter().filter(|&&v| v != 0).count();
+        assert_eq!(count(&canonical), count(&sudoku));
+        assert_eq!(canonical.canonical(), canonical);
+
+        let moved = Transform {
+            transpose: true,
+            rows: [8, 6, 7, 2, 1, 0, 3, 5, 4],
+            cols: [1, 0, 2, 6, 7, 8, 5, 4, 3],
+            digits: [0, 9, 3, 1, 2, 8, 7, 4, 5, 6],
+        };
+        assert!(moved.is_valid());
+        let other = sudoku.apply_transform(&moved);
+        assert_ne!(other, sudoku);
+        assert!(other.is_equivalent(&sudoku));
+        assert_eq!(other.canonical(), canonical);
+
+        // A different puzzle isn't.
+        let different = Sudoku::from_txt("test/top95.txt".to_string())[1];
+        assert!(!different.is_equivalent(&sudoku));
+    }
+
+    #[test]
+    fn test_canonical_keeps_possibles() {
+        // Half solved, with possible values already taken out. Nothing new gets
+        // solved on the way and every box keeps as many possible values as it had.
+        let sudoku = Sudoku::from_possibles("test/xwing_ready.ss".to_string());
+        let canonical = sudoku.canonical();
+        let counts = |s: &Sudoku| {
+            let mut counts: Vec<usize> = (0..81)
+                .map(|idx| s.box_at(idx).get_possibles().len())
+                .collect();
+            counts.sort();
+            counts
+        };
+        assert_eq!(counts(&canonical), counts(&sudoku));
+        assert!(canonical.is_equivalent(&sudoku));
+        assert_eq!(canonical.canonical(), canonical);
+    }
+
+    #[test]
+    fn test_moves() {
+        let sudoku = Sudoku::parse_line(LINE).unwrap();
+        let values = sudoku.values();
+        let at = |s: &Sudoku, row: usize, col: usize| s.values()[row * 9 + col];
+
+        // r1c1 is a 4, r1c7 an 8 and r2c2 a 3.
+        let rotated = sudoku.apply_transform(&Transform::rotate());
+        assert_eq!(at(&rotated, 0, 8), 4);
+        assert_eq!(at(&rotated, 6, 8), 8);
+        let four_turns = (0..4).fold(sudoku, |s, _| s.apply_transform(&Transform::rotate()));
+        assert_eq!(four_turns, sudoku);
+
+        assert_eq!(at(&sudoku.apply_transform(&Transform::mirror()), 0, 8), 4);
+        assert_eq!(at(&sudoku.apply_transform(&Transform::flip()), 8, 0), 4);
+        assert_eq!(
+            at(&sudoku.apply_transform(&Transform::transpose()), 6, 0),
+            8
+        );
+        assert_eq!(
+            at(&sudoku.apply_transform(&Transform::swap_rows(0, 1)), 1, 0),
+            4
+        );
+        assert_eq!(
+            at(&sudoku.apply_transform(&Transform::swap_cols(1, 2)), 1, 2),
+            3
+        );
+        assert_eq!(
+            at(&sudoku.apply_transform(&Transform::swap_bands(0, 2)), 6, 0),
+            4
+        );
+        assert_eq!(
+            at(&sudoku.apply_transform(&Transform::swap_stacks(0, 2)), 0, 6),
+            4
+        );
+
+        let relabel = Transform::relabel([0, 9, 8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(at(&sudoku.apply_transform(&relabel), 0, 0), 6);
+
+        for transform in [Transform::rotate(), Transform::swap_bands(1, 2), relabel] {
+            let moved = sudoku.apply_transform(&transform);
+            assert_ne!(moved.values(), values);
+            assert!(moved.is_equivalent(&sudoku));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_swap_rows_across_bands() {
+        Transform::swap_rows(2, 3);
+    }
+
+    #[test]
+    fn test_random_transform() {
+        use crate::rating::rate;
+        use crate::sudoku_builder::has_unique_solution;
+        use rand_chacha::ChaCha8Rng;
+
+        let sudoku = Sudoku::from_ss("test/simple.ss".to_string()).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        let first = sudoku.transform(&mut rng);
+        let second = sudoku.transform(&mut rng);
+        assert_ne!(first, second);
+
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        assert_eq!(sudoku.transform(&mut rng), first);
+
+        for variant in [first, second] {
+            assert!(variant.is_equivalent(&sudoku));
+            assert!(has_unique_solution(&variant));
+            assert_eq!(
+                rate(&variant).unwrap().hardest,
+                rate(&sudoku).unwrap().hardest
+            );
+        }
+    }
+}